@@ -1,9 +1,16 @@
+use crate::search::Heuristic;
 use clap::{Parser, ValueEnum};
 use strum_macros::Display;
 
 #[derive(Parser, Debug, Clone, Display, ValueEnum, PartialEq, Eq)]
 pub enum EngineMode {
     PERFT,
+    BENCH,
+    UCI,
+    MATE,
+    PROBLEM,
+    PGN,
+    ANALYSE,
 }
 
 #[derive(Parser, Debug)]
@@ -24,6 +31,39 @@ pub struct Args {
 
     #[arg(long, default_value_t = true)]
     pub debug: bool,
+
+    // generate moves through the staged move picker instead of all at once
+    #[arg(long, default_value_t = false)]
+    pub staged: bool,
+
+    // search threads for bench and analyse
+    #[arg(long, default_value_t = 1)]
+    pub threads: usize,
+
+    // search heuristics to switch off in bench, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub disable: Vec<Heuristic>,
+
+    // how many of the best lines analyse mode shows
+    #[arg(long, default_value_t = 3)]
+    pub lines: usize,
+
+    // what a problem asks for: #2, h#3 or s#2
+    #[arg(long, default_value = "#2")]
+    pub stipulation: String,
+
+    // a twin of the problem's diagram, e.g. "move e1 e2, add Qa1", repeated for more twins
+    #[arg(long)]
+    pub twin: Vec<String>,
+
+    // PGN file to read in pgn mode
+    #[arg(long)]
+    pub pgn: Option<String>,
+
+    // PGN file to write the games read in pgn mode to, the mating line found in mate mode, or
+    // the lines found in analyse mode
+    #[arg(long)]
+    pub pgn_out: Option<String>,
 }
 
 impl Args {
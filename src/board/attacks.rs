@@ -0,0 +1,127 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::enums::*;
+
+// pieces of one color pinned to their own king, and the ray each of them is stuck on: the squares
+// between the king and the pinning piece plus the pinner itself, which are the only squares a
+// pinned piece may still move to
+#[derive(Debug, Clone, Copy)]
+pub struct Pins {
+    pub pinned: Bitboard,
+    rays: [Bitboard; 64],
+}
+
+impl Pins {
+    pub fn is_pinned(&self, square: SQUARE) -> bool {
+        self.pinned.is_set(square.index())
+    }
+
+    // the squares the piece on square can move to without exposing its king, every square if it
+    // isn't pinned
+    pub fn ray(&self, square: SQUARE) -> Bitboard {
+        self.rays[square.index()]
+    }
+}
+
+impl<'a> Board<'a> {
+    // pieces of both colors attacking square, as if only the pieces in occupancy were on the
+    // board. Taking pieces out of occupancy uncovers the sliders behind them, which is what SEE
+    // needs to play out a capture sequence
+    pub fn attackers_to(&self, square: SQUARE, occupancy: Bitboard) -> Bitboard {
+        let lookup_table = self.lookup_table;
+        let diagonal =
+            self.pieces_of_type(PieceType::BISHOP) | self.pieces_of_type(PieceType::QUEEN);
+        let straight = self.pieces_of_type(PieceType::ROOK) | self.pieces_of_type(PieceType::QUEEN);
+
+        // a pawn on square attacks exactly the squares enemy pawns would attack it from
+        let pawns = (lookup_table.get_pawn_attacks(square, COLOR::WHITE)
+            & self.pieces_of(COLOR::BLACK, PieceType::PAWN))
+            | (lookup_table.get_pawn_attacks(square, COLOR::BLACK)
+                & self.pieces_of(COLOR::WHITE, PieceType::PAWN));
+
+        // the other pieces attack the same way whatever their color
+        let color = COLOR::WHITE;
+        let attackers = pawns
+            | lookup_table.get_knight_moves(square, color) & self.pieces_of_type(PieceType::KNIGHT)
+            | lookup_table.get_king_moves(square, color) & self.pieces_of_type(PieceType::KING)
+            | lookup_table.get_bishop_moves(square, color, occupancy.bits()) & diagonal
+            | lookup_table.get_rook_moves(square, color, occupancy.bits()) & straight;
+
+        attackers & occupancy
+    }
+
+    // enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> Bitboard {
+        let king_square = self.king_square(self.to_move);
+
+        self.attackers_to(king_square, self.occupancy())
+            & self.occupancy_of_color(self.to_move.opposite())
+    }
+
+    // pieces of the given color that can't leave the line between their king and an enemy slider
+    pub fn pinned(&self, color: COLOR) -> Pins {
+        let mut pins = Pins {
+            pinned: Bitboard::default(),
+            rays: [Bitboard::new(!0); 64],
+        };
+
+        let king_square = self.king_square(color);
+        let enemy = color.opposite();
+        let enemy_occupancy = self.occupancy_of_color(enemy).bits();
+
+        // 1. Enemy sliders that would see the king if none of our pieces were in the way
+        let mut snipers = (self
+            .lookup_table
+            .get_bishop_moves(king_square, color, enemy_occupancy)
+            & (self.pieces_of(enemy, PieceType::BISHOP) | self.pieces_of(enemy, PieceType::QUEEN)))
+            | (self
+                .lookup_table
+                .get_rook_moves(king_square, color, enemy_occupancy)
+                & (self.pieces_of(enemy, PieceType::ROOK)
+                    | self.pieces_of(enemy, PieceType::QUEEN)));
+
+        // 2. Those with exactly one of our pieces between them and the king pin it
+        while let Some(index) = snipers.pop_lsb() {
+            let sniper = SQUARE::from(index);
+            let between = self.lookup_table.get_between(king_square, sniper);
+            let blockers = between & self.occupancy();
+
+            if blockers.count() == 1 {
+                let pinned = SQUARE::from_bits(blockers.bits());
+                pins.pinned.set(pinned.index());
+                pins.rays[pinned.index()] = between | Bitboard::new(sniper.bits());
+            }
+        }
+
+        pins
+    }
+
+    // sliders of both colors that attack square through exactly one piece in occupancy, and would
+    // join the attack once that piece moves or is captured
+    pub fn xray_attackers(&self, square: SQUARE, occupancy: Bitboard) -> Bitboard {
+        let color = COLOR::WHITE;
+        let diagonal =
+            self.pieces_of_type(PieceType::BISHOP) | self.pieces_of_type(PieceType::QUEEN);
+        let straight = self.pieces_of_type(PieceType::ROOK) | self.pieces_of_type(PieceType::QUEEN);
+
+        // 1. Direct attacks, whose first piece on each ray is the one we look through
+        let bishop_rays = self
+            .lookup_table
+            .get_bishop_moves(square, color, occupancy.bits());
+        let rook_rays = self
+            .lookup_table
+            .get_rook_moves(square, color, occupancy.bits());
+
+        // 2. The same rays with those first pieces lifted off the board
+        let bishop_xrays =
+            self.lookup_table
+                .get_bishop_moves(square, color, (occupancy & !bishop_rays).bits())
+                & !bishop_rays;
+        let rook_xrays =
+            self.lookup_table
+                .get_rook_moves(square, color, (occupancy & !rook_rays).bits())
+                & !rook_rays;
+
+        (bishop_xrays & diagonal | rook_xrays & straight) & occupancy
+    }
+}
@@ -0,0 +1,129 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::lookup_table::LookupTable;
+use rand::Rng;
+
+// knight placements on the five squares left after placing bishops and queen, in Scharnagl order
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl<'a> Board<'a> {
+    // White's back rank for start position 0..960 using Scharnagl's numbering (518 is standard chess)
+    pub fn chess960_back_rank(index: u16) -> [PieceType; 8] {
+        assert!(index < 960, "Chess960 positions are numbered 0 to 959");
+
+        let mut back_rank = [PieceType::EMPTY; 8];
+        let mut n = index as usize;
+
+        // light squared bishop on b, d, f or h, then dark squared bishop on a, c, e or g
+        back_rank[(n % 4) * 2 + 1] = PieceType::BISHOP;
+        n /= 4;
+        back_rank[(n % 4) * 2] = PieceType::BISHOP;
+        n /= 4;
+
+        // queen on one of the six remaining squares
+        let empty = |back_rank: &[PieceType; 8]| -> Vec<usize> {
+            (0..8)
+                .filter(|i| back_rank[*i] == PieceType::EMPTY)
+                .collect()
+        };
+        back_rank[empty(&back_rank)[n % 6]] = PieceType::QUEEN;
+        n /= 6;
+
+        // knights on two of the five remaining squares
+        let (first, second) = KNIGHT_PLACEMENTS[n];
+        let remaining = empty(&back_rank);
+        back_rank[remaining[first]] = PieceType::KNIGHT;
+        back_rank[remaining[second]] = PieceType::KNIGHT;
+
+        // rook, king, rook on whatever is left so the king always sits between the rooks
+        let remaining = empty(&back_rank);
+        back_rank[remaining[0]] = PieceType::ROOK;
+        back_rank[remaining[1]] = PieceType::KING;
+        back_rank[remaining[2]] = PieceType::ROOK;
+
+        back_rank
+    }
+
+    pub fn chess960_position(index: u16, lookup_table: &'a LookupTable) -> Board<'a> {
+        let back_rank = Board::chess960_back_rank(index);
+
+        let white: String = back_rank
+            .iter()
+            .map(|piece_type| piece_type.of_color(COLOR::WHITE) as u8 as char)
+            .collect();
+        let black = white.to_ascii_lowercase();
+
+        let fen = format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black, white);
+
+        let mut board = Board::from_fen(&fen, lookup_table);
+        board.chess960 = true;
+        board
+    }
+
+    // any of the 960 start positions, all equally likely
+    pub fn random_chess960(rng: &mut impl Rng, lookup_table: &'a LookupTable) -> Board<'a> {
+        Board::chess960_position(rng.gen_range(0..960), lookup_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // bishops on opposite colours and the king between the rooks
+    fn is_chess960_back_rank(back_rank: &[PieceType; 8]) -> bool {
+        let files = |piece_type: PieceType| -> Vec<usize> {
+            (0..8).filter(|i| back_rank[*i] == piece_type).collect()
+        };
+        let bishops = files(PieceType::BISHOP);
+        let rooks = files(PieceType::ROOK);
+        let king = files(PieceType::KING);
+
+        bishops.len() == 2
+            && bishops[0] % 2 != bishops[1] % 2
+            && rooks.len() == 2
+            && king.len() == 1
+            && rooks[0] < king[0]
+            && king[0] < rooks[1]
+    }
+
+    #[test]
+    fn every_back_rank_is_legal_and_different() {
+        let back_ranks: Vec<[PieceType; 8]> = (0..960).map(Board::chess960_back_rank).collect();
+        assert!(back_ranks.iter().all(is_chess960_back_rank));
+
+        for (i, back_rank) in back_ranks.iter().enumerate() {
+            assert!(!back_ranks[i + 1..].contains(back_rank));
+        }
+    }
+
+    #[test]
+    fn random_positions_are_chess960() {
+        let lookup_table = LookupTable::new();
+        let mut rng = StdRng::seed_from_u64(960);
+
+        for _ in 0..50 {
+            let board = Board::random_chess960(&mut rng, &lookup_table);
+            assert!(board.chess960);
+            assert!(board.validate().is_empty());
+
+            let back_rank: Vec<PieceType> = (0..8)
+                .map(|file| board.piece_at_index(file).piece_type())
+                .collect();
+            assert!(is_chess960_back_rank(&back_rank.try_into().unwrap()));
+        }
+    }
+}
@@ -1,68 +1,189 @@
+use crate::bitboard::*;
 use crate::board::Board;
-use crate::enums::ENDGAME;
+use crate::enums::*;
+
+const DARK_SQUARES: u64 = 0xAA55AA55AA55AA55;
 
 impl<'a> Board<'a> {
+    // Neither side has the material to ever mate, whatever the moves:
+    // - lone kings
+    // - king and knight against king
+    // - kings and any number of bishops, all on squares of the same color
     pub fn insufficient_material(&self) -> bool {
-        let white_pieces = self.white_occupancy().count();
-        let black_pieces = self.black_occupancy().count();
-
-        // If both sides only have one of the following its insufficient material
-        // - lone king
-        // - king and bishop
-        // - king and knight
-
-        if white_pieces <= 2 && black_pieces <= 2 {
-            let w_has_lone_k = white_pieces == 1;
-            let b_has_lone_k = black_pieces == 1;
-            let w_has_b = self.white_bishops.not_empty();
-            let w_has_n = self.white_knights.not_empty();
-            let b_has_b = self.black_bishops.not_empty();
-            let b_has_n = self.black_knights.not_empty();
-
-            let b_has_one_of_three = b_has_b | b_has_n | b_has_lone_k;
-            let w_has_one_of_three = w_has_b | w_has_n | w_has_lone_k;
-
-            return w_has_one_of_three && b_has_one_of_three;
+        let heavy = self.pieces_of_type(PieceType::PAWN)
+            | self.pieces_of_type(PieceType::ROOK)
+            | self.pieces_of_type(PieceType::QUEEN);
+        if heavy.any() {
+            return false;
         }
 
-        false
-    }
+        let knights = self.pieces_of_type(PieceType::KNIGHT);
+        let bishops = self.pieces_of_type(PieceType::BISHOP);
+        let dark_bishops = bishops & Bitboard::new(DARK_SQUARES);
 
-    pub fn threefold_repetition(&self) -> bool {
-        // todo implement
-        false
+        match knights.count() {
+            0 => dark_bishops.empty() || dark_bishops == bishops,
+            1 => bishops.empty(),
+            _ => false,
+        }
     }
 
-    pub fn endgame(&self) -> Option<ENDGAME> {
-        let moves = self.generate_moves_for_color(self.to_move);
-        let in_check = self.in_check(self.to_move);
-        let no_moves = moves.is_empty();
+    // Only kings and pawns, where every pawn is stuck behind an enemy pawn and neither king can
+    // ever get to an enemy pawn: nothing will be captured or promoted again, so nobody can mate
+    pub fn blocked_position(&self) -> bool {
+        let pawns =
+            [COLOR::WHITE, COLOR::BLACK].map(|color| self.pieces_of(color, PieceType::PAWN));
+        let kings = self.pieces_of_type(PieceType::KING);
+
+        if self.occupancy() != (pawns[0] | pawns[1] | kings) || self.en_passant_target.is_some() {
+            return false;
+        }
+
+        // 1. No pawn can push or capture
+        let mut attacked_by = [Bitboard::default(); 2];
+        for color in [COLOR::WHITE, COLOR::BLACK] {
+            let enemy_pawns = pawns[color.opposite().index()];
+            let mut own_pawns = pawns[color.index()];
+
+            while let Some(index) = own_pawns.pop_lsb() {
+                let square = SQUARE::from(index);
+                let attacks = self.lookup_table.get_pawn_attacks(square, color);
+
+                let blocked = square
+                    .forward(color)
+                    .is_some_and(|front| enemy_pawns.is_set(front.index()));
+                if !blocked || (attacks & enemy_pawns).any() {
+                    return false;
+                }
 
-        // 1. Checkmate
-        if no_moves && in_check {
-            return Some(ENDGAME::Checkmate);
+                attacked_by[color.index()] |= attacks;
+            }
         }
 
-        // 2. Stalemate
-        if no_moves && !in_check {
-            return Some(ENDGAME::Stalemate);
+        // 2. Neither king can reach an undefended enemy pawn, walking only on squares no enemy
+        // pawn attacks (the pawns never move, so those squares never change)
+        for color in [COLOR::WHITE, COLOR::BLACK] {
+            let safe = !attacked_by[color.opposite().index()] & !pawns[color.index()];
+
+            let mut reachable = self.pieces_of(color, PieceType::KING);
+            loop {
+                let mut frontier = reachable;
+                let mut next = reachable;
+                while let Some(index) = frontier.pop_lsb() {
+                    next |= self.lookup_table.get_king_moves(SQUARE::from(index), color) & safe;
+                }
+
+                if next == reachable {
+                    break;
+                }
+                reachable = next;
+            }
+
+            if (reachable & pawns[color.opposite().index()]).any() {
+                return false;
+            }
         }
 
-        // 3. Insufficient material
+        true
+    }
+
+    // Game endings that don't need a claim, in FIDE's order of precedence: a mate on the
+    // 150th ply still counts as a mate. Repetitions need the game history, see Game::adjudicate
+    pub fn endgame(&self) -> Option<ENDGAME> {
+        // 1. Checkmate and 2. Stalemate, which only need to know that no legal move exists
+        if !self.has_legal_move() {
+            return match self.in_check(self.to_move) {
+                true => Some(ENDGAME::Checkmate),
+                false => Some(ENDGAME::Stalemate),
+            };
+        }
+
+        // 3. Dead positions
         if self.insufficient_material() {
             return Some(ENDGAME::InsufficientMaterial);
         }
-
-        // 4. Fifty-move rule
-        if self.halfmove_clock >= 100 {
-            return Some(ENDGAME::FiftyMoveRule);
+        if self.blocked_position() {
+            return Some(ENDGAME::DeadPosition);
         }
 
-        // 5. Threefold repetition
-        if self.threefold_repetition() {
-            return Some(ENDGAME::ThreefoldRepetition);
+        // 4. Seventy-five-move rule
+        if self.halfmove_clock >= 150 {
+            return Some(ENDGAME::SeventyFiveMoveRule);
         }
 
         None
     }
+
+    // the draw the side to move could claim from the position alone, see Game::claimable_draw
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        match self.halfmove_clock >= 100 {
+            true => Some(DrawClaim::FiftyMoveRule),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::enums::*;
+    use crate::lookup_table::LookupTable;
+
+    fn endgame(fen: &str) -> Option<ENDGAME> {
+        let lookup_table = LookupTable::new();
+        Board::from_fen(fen, &lookup_table).endgame()
+    }
+
+    #[test]
+    fn bishops_on_one_colour_are_dead() {
+        // c1 and f8 are both dark squares
+        assert_eq!(
+            endgame("5b1k/8/8/8/8/8/8/K1B5 w - - 0 1"),
+            Some(ENDGAME::InsufficientMaterial)
+        );
+        // e8 is light, so a mate in the corner can still be helped along
+        assert_eq!(endgame("4b2k/8/8/8/8/8/8/K1B5 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn locked_pawn_chain_is_dead() {
+        // every square of the fourth and fifth ranks is a pawn or guarded by one
+        assert_eq!(
+            endgame("4k3/8/8/p1p1p1p1/P1P1P1P1/8/8/4K3 w - - 0 1"),
+            Some(ENDGAME::DeadPosition)
+        );
+        // here the white king walks around by the h-file and takes f5
+        assert_eq!(endgame("4k3/8/8/1p1p1p2/1P1P1P2/8/8/4K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn mate_beats_the_seventy_five_move_rule() {
+        assert_eq!(
+            endgame("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 90"),
+            Some(ENDGAME::Checkmate)
+        );
+        assert_eq!(
+            endgame("6k1/5ppp/8/8/8/8/8/R5K1 b - - 150 90"),
+            Some(ENDGAME::SeventyFiveMoveRule)
+        );
+    }
+
+    #[test]
+    fn fifty_moves_are_claimed_not_adjudicated() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 b - - 100 70", &lookup_table);
+        assert_eq!(board.claimable_draw(), Some(DrawClaim::FiftyMoveRule));
+        assert_eq!(board.endgame(), None);
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 b - - 99 70", &lookup_table);
+        assert_eq!(board.claimable_draw(), None);
+    }
+
+    #[test]
+    fn halfmove_clock_saturates() {
+        let lookup_table = LookupTable::new();
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 255 200", &lookup_table);
+        board.make_move(board.parse_uci_move("a1a2").unwrap());
+        assert_eq!(board.halfmove_clock, 255);
+    }
 }
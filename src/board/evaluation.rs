@@ -0,0 +1,228 @@
+use crate::board::Board;
+use crate::enums::*;
+
+// Piece-square tables from Tomasz Michniewski's simplified evaluation function, written the way
+// the board looks from white's side: a8 first, h1 last. White pieces read them through
+// square ^ 56, black pieces straight, which mirrors the table for them
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+// the king hides behind its pawns while there are pieces around, and walks to the center once
+// they are gone
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// game phase weights: 24 with all pieces on the board, 0 with only kings and pawns left
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+const BISHOP_PAIR: i32 = 30;
+
+// a passed pawn's bonus by how far it has come, counted in ranks from its own side
+const PASSED_PAWN: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+const ISOLATED_PAWN: i32 = -10;
+// per own pawn next to the king, while there are pieces around to attack it
+const PAWN_SHIELD: i32 = 10;
+// per step the attacking king takes towards a bare king
+const MOP_UP: i32 = 5;
+
+impl<'a> Board<'a> {
+    // how much of the middlegame is left, between 0 and MAX_PHASE (promotions can't push it over)
+    pub fn phase(&self) -> i32 {
+        let phase: i32 = PIECE_TYPES
+            .iter()
+            .map(|piece_type| {
+                PHASE_WEIGHTS[piece_type.index()] * self.pieces_of_type(*piece_type).count() as i32
+            })
+            .sum();
+
+        phase.min(MAX_PHASE)
+    }
+
+    // static evaluation in centipawns from the side to move's point of view: material, piece
+    // placement, pawn structure and king safety, with the king's terms blended from middlegame to
+    // endgame by phase
+    pub fn evaluate(&self) -> i32 {
+        let phase = self.phase();
+        let mut score = 0;
+
+        for color in [COLOR::WHITE, COLOR::BLACK] {
+            let sign = match color {
+                COLOR::WHITE => 1,
+                COLOR::BLACK => -1,
+            };
+            let flip = match color {
+                COLOR::WHITE => 56,
+                COLOR::BLACK => 0,
+            };
+
+            let mut side_score = 0;
+            for piece_type in PIECE_TYPES {
+                let mut pieces = self.pieces_of(color, piece_type);
+
+                while let Some(index) = pieces.pop_lsb() {
+                    let square = index ^ flip;
+
+                    side_score += match piece_type {
+                        PieceType::PAWN => PieceType::PAWN.value() + PAWN_TABLE[square],
+                        PieceType::KNIGHT => PieceType::KNIGHT.value() + KNIGHT_TABLE[square],
+                        PieceType::BISHOP => PieceType::BISHOP.value() + BISHOP_TABLE[square],
+                        PieceType::ROOK => PieceType::ROOK.value() + ROOK_TABLE[square],
+                        PieceType::QUEEN => PieceType::QUEEN.value() + QUEEN_TABLE[square],
+                        PieceType::KING => {
+                            (KING_MIDDLEGAME_TABLE[square] * phase
+                                + KING_ENDGAME_TABLE[square] * (MAX_PHASE - phase))
+                                / MAX_PHASE
+                        }
+                        PieceType::EMPTY => 0,
+                    };
+                }
+            }
+
+            if self.pieces_of(color, PieceType::BISHOP).count() >= 2 {
+                side_score += BISHOP_PAIR;
+            }
+
+            side_score += self.pawn_structure(color, phase);
+            side_score += self.pawn_shield(color, phase);
+            side_score += self.mop_up(color);
+
+            score += sign * side_score;
+        }
+
+        match self.to_move {
+            COLOR::WHITE => score,
+            COLOR::BLACK => -score,
+        }
+    }
+
+    // passed pawns, worth more the further they've come and the fewer pieces are left to stop
+    // them, and isolated pawns, which no pawn of their own can ever defend
+    fn pawn_structure(&self, color: COLOR, phase: i32) -> i32 {
+        let own_pawns = self.pieces_of(color, PieceType::PAWN);
+        let enemy_pawns = self.pieces_of(color.opposite(), PieceType::PAWN);
+
+        let mut score = 0;
+        let mut pawns = own_pawns;
+        while let Some(index) = pawns.pop_lsb() {
+            let square = SQUARE::from(index);
+
+            let passed_mask = self.lookup_table.get_passed_pawn_mask(square, color);
+            if (passed_mask & enemy_pawns).empty() {
+                let rank = match color {
+                    COLOR::WHITE => square.rank().index(),
+                    COLOR::BLACK => 7 - square.rank().index(),
+                };
+                // half the bonus in the middlegame, all of it once the pieces are gone
+                score += PASSED_PAWN[rank] * (2 * MAX_PHASE - phase) / (2 * MAX_PHASE);
+            }
+
+            if (self.lookup_table.get_adjacent_files(square) & own_pawns).empty() {
+                score += ISOLATED_PAWN;
+            }
+        }
+
+        score
+    }
+
+    // own pawns around the king, which only matter while the enemy has pieces to attack with
+    fn pawn_shield(&self, color: COLOR, phase: i32) -> i32 {
+        let zone = self.lookup_table.get_king_zone(self.king_square(color));
+        let shield = (zone & self.pieces_of(color, PieceType::PAWN)).count() as i32;
+
+        PAWN_SHIELD * shield * phase / MAX_PHASE
+    }
+
+    // Against a bare king, the attacking king has to come close to help mate it, which the
+    // tables alone don't ask for, as they pull both kings to the center
+    fn mop_up(&self, color: COLOR) -> i32 {
+        let enemy = color.opposite();
+        if self.occupancy_of_color(enemy).count() > 1 || !self.has_non_pawn_material(color) {
+            return 0;
+        }
+
+        let distance = self
+            .lookup_table
+            .get_manhattan_distance(self.king_square(color), self.king_square(enemy));
+
+        MOP_UP * (14 - distance as i32)
+    }
+}
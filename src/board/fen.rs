@@ -1,15 +1,46 @@
-use crate::board::Board;
+use crate::board::{Board, STARTING_FEN};
 use crate::enums::*;
 use crate::lookup_table::LookupTable;
 use strum::IntoEnumIterator;
 
 impl<'a> Board<'a> {
+    // panics on malformed FEN, but accepts any position it describes (see from_fen_strict)
     pub fn from_fen(fen: &str, lookup_table: &'a LookupTable) -> Board<'a> {
+        match Board::parse_fen(fen, false, lookup_table) {
+            Ok(board) => board,
+            Err(error) => panic!("{}: {}", error, fen),
+        }
+    }
+
+    // only accepts positions that can come up in a game, reporting every violation otherwise.
+    // In chess960 a king off the e-file is fine, so KQkq means its outermost rooks
+    pub fn from_fen_strict(
+        fen: &str,
+        chess960: bool,
+        lookup_table: &'a LookupTable,
+    ) -> Result<Board<'a>, Vec<PositionError>> {
+        let board = Board::parse_fen(fen, chess960, lookup_table).map_err(|error| vec![error])?;
+
+        let errors = board.validate();
+        match errors.is_empty() {
+            true => Ok(board),
+            false => Err(errors),
+        }
+    }
+
+    pub fn parse_fen(
+        fen: &str,
+        chess960: bool,
+        lookup_table: &'a LookupTable,
+    ) -> Result<Board<'a>, PositionError> {
+        let invalid = |reason: String| PositionError::InvalidFen(reason);
         let mut board = Board::new(lookup_table);
 
         // split the board configuration from metadata
-        let fen = fen.trim().split(" ").into_iter().collect::<Vec<&str>>();
-        assert_eq!(fen.len(), 6, "Invalid FEN string: {}", fen.join(" "));
+        let fen = fen.split_whitespace().collect::<Vec<&str>>();
+        if fen.len() != 6 {
+            return Err(invalid(format!("expected 6 fields, found {}", fen.len())));
+        }
 
         let board_data = fen[0];
         let turn = fen[1];
@@ -21,55 +52,77 @@ impl<'a> Board<'a> {
         board.to_move = match turn {
             "w" => COLOR::WHITE,
             "b" => COLOR::BLACK,
-            _ => panic!("Invalid turn: {}", turn),
+            _ => return Err(invalid(format!("invalid turn {}", turn))),
         };
 
-        board.castling_rights = CastlingRights::from_fen(castling_rights);
-
         board.en_passant_target = match en_passant_target {
             "-" => None,
-            _ => SQUARE::from_string(en_passant_target),
+            _ => match SQUARE::from_string(en_passant_target) {
+                Some(square) => Some(square),
+                None => {
+                    return Err(invalid(format!(
+                        "invalid en passant square {}",
+                        en_passant_target
+                    )))
+                }
+            },
         };
 
-        board.halfmove_clock = halfmove_clock.parse().expect("Invalid halfmove clock");
-        board.fullmove_number = fullmove_number.parse().expect("Invalid fullmove number");
+        board.halfmove_clock = halfmove_clock
+            .parse()
+            .map_err(|_| invalid(format!("invalid halfmove clock {}", halfmove_clock)))?;
+        board.fullmove_number = fullmove_number
+            .parse()
+            .map_err(|_| invalid(format!("invalid fullmove number {}", fullmove_number)))?;
 
         // Reverse the order of ranks in the FEN string so that chars go from A1..=H8
-        let board_data: String = board_data
-            .split("/")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .rev()
-            .flat_map(|s: &str| s.chars())
-            .collect();
-
-        let mut index = 0;
-        for c in board_data.chars() {
-            match c {
-                'P' => board.white_pawns.set(index),
-                'N' => board.white_knights.set(index),
-                'B' => board.white_bishops.set(index),
-                'R' => board.white_rooks.set(index),
-                'Q' => board.white_queens.set(index),
-                'K' => board.white_king.set(index),
-
-                'p' => board.black_pawns.set(index),
-                'n' => board.black_knights.set(index),
-                'b' => board.black_bishops.set(index),
-                'r' => board.black_rooks.set(index),
-                'q' => board.black_queens.set(index),
-                'k' => board.black_king.set(index),
-
-                num => {
-                    let num = num.to_digit(10).expect("Invalid FEN character");
-                    assert!(num >= 1 && num <= 8, "Invalid FEN character");
-                    index += num as usize - 1;
+        let ranks = board_data.split("/").collect::<Vec<&str>>();
+        if ranks.len() != 8 {
+            return Err(invalid(format!("expected 8 ranks, found {}", ranks.len())));
+        }
+
+        for (rank_index, rank) in ranks.into_iter().rev().enumerate() {
+            let mut file_index = 0;
+            for c in rank.chars() {
+                match (PIECE::from_char(c), c.to_digit(10)) {
+                    (Some(piece), _) if file_index < 8 => {
+                        board.add_piece(rank_index * 8 + file_index, piece);
+                        file_index += 1;
+                    }
+                    (None, Some(num @ 1..=8)) => file_index += num as usize,
+                    _ => return Err(invalid(format!("invalid rank {}", rank))),
                 }
             }
-            index += 1;
+            if file_index != 8 {
+                return Err(invalid(format!("rank {} doesn't have 8 squares", rank)));
+            }
         }
 
-        board
+        if castling_rights != "-"
+            && !castling_rights
+                .chars()
+                .all(|c| "KQkq".contains(c) || ('A'..='H').contains(&c.to_ascii_uppercase()))
+        {
+            return Err(invalid(format!(
+                "invalid castling rights {}",
+                castling_rights
+            )));
+        }
+
+        // castling rights refer to the rooks on the board, so they are read after placing pieces
+        board.castling_rights = CastlingRights::from_fen(castling_rights, &board);
+
+        // rook file letters or rooks off the a/h corners only make sense in chess960. Outside of
+        // it, a king off the e-file with KQkq rights is read as a (broken) standard position for
+        // validate to report
+        let shredder_fen = castling_rights
+            .chars()
+            .any(|c| c != '-' && !"KQkq".contains(c));
+        board.chess960 = chess960 || shredder_fen || !board.castling_rights.is_standard();
+
+        board.key = board.compute_key();
+
+        Ok(board)
     }
 
     pub fn to_fen(&self) -> String {
@@ -110,7 +163,7 @@ impl<'a> Board<'a> {
 
         // adding castling rights
         fen.push(' ');
-        fen.push_str(&self.castling_rights.to_fen());
+        fen.push_str(&self.castling_rights.to_fen(self));
 
         // adding en passant target
         fen.push(' ');
@@ -131,9 +184,6 @@ impl<'a> Board<'a> {
     }
 
     pub fn starting_position(lookup_table: &LookupTable) -> Board {
-        Board::from_fen(
-            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
-            lookup_table,
-        )
+        Board::from_fen(STARTING_FEN, lookup_table)
     }
 }
@@ -1,14 +1,24 @@
+mod attacks;
 mod bits;
+mod chess960;
 mod display;
 mod endgame_detection;
+mod evaluation;
 mod fen;
 mod move_generation;
 mod move_validation;
 mod perft;
 mod piece_movement;
+mod position_validation;
+mod san;
+mod see;
+mod uci_move;
+mod zobrist;
 
 use crate::{bitboard::*, enums::*, lookup_table::*};
 
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 #[derive(Debug, Clone)]
 pub struct Board<'a> {
     pub to_move: COLOR,
@@ -18,26 +28,23 @@ pub struct Board<'a> {
 
     pub en_passant_target: Option<SQUARE>,
 
-    pub white_pawns: Bitboard,
-    pub white_knights: Bitboard,
-    pub white_bishops: Bitboard,
-    pub white_rooks: Bitboard,
-    pub white_queens: Bitboard,
-    pub white_king: Bitboard,
+    // chess960 only changes how castles are written (king takes rook), the rules are shared
+    pub chess960: bool,
+
+    // indexed by [color][piece type], kept in sync with the two below by add_piece/remove_piece
+    pub pieces: [[Bitboard; 6]; 2],
+    pub color_occupancy: [Bitboard; 2],
+    pub mailbox: [PIECE; 64],
 
-    pub black_pawns: Bitboard,
-    pub black_knights: Bitboard,
-    pub black_bishops: Bitboard,
-    pub black_rooks: Bitboard,
-    pub black_queens: Bitboard,
-    pub black_king: Bitboard,
+    // zobrist key of the position, updated by add_piece/remove_piece and make_move
+    pub key: u64,
 
     pub lookup_table: &'a LookupTable,
 }
 
 impl<'a> Board<'a> {
     pub fn new(lookup_table: &'a LookupTable) -> Board<'a> {
-        Self {
+        let mut board = Self {
             to_move: COLOR::WHITE,
             castling_rights: CastlingRights::default(),
             halfmove_clock: 0,
@@ -45,21 +52,17 @@ impl<'a> Board<'a> {
 
             en_passant_target: None,
 
-            white_pawns: Bitboard::default(),
-            white_knights: Bitboard::default(),
-            white_bishops: Bitboard::default(),
-            white_rooks: Bitboard::default(),
-            white_queens: Bitboard::default(),
-            white_king: Bitboard::default(),
+            chess960: false,
+
+            pieces: [[Bitboard::default(); 6]; 2],
+            color_occupancy: [Bitboard::default(); 2],
+            mailbox: [PIECE::Empty; 64],
 
-            black_pawns: Bitboard::default(),
-            black_knights: Bitboard::default(),
-            black_bishops: Bitboard::default(),
-            black_rooks: Bitboard::default(),
-            black_queens: Bitboard::default(),
-            black_king: Bitboard::default(),
+            key: 0,
 
             lookup_table,
-        }
+        };
+        board.key = board.compute_key();
+        board
     }
 }
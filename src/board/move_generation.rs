@@ -1,141 +1,311 @@
+use crate::bitboard::*;
 use crate::board::Board;
 use crate::enums::*;
-use crate::r#move::Move;
+use crate::r#move::*;
 
 impl<'a> Board<'a> {
-    fn generate_moves_for_piece(&self, piece: PIECE) -> Vec<Move> {
-        let mut moves = Vec::new();
+    // squares attacked by a piece of the given type and color standing on square
+    pub fn attacks_from(
+        &self,
+        piece_type: PieceType,
+        square: SQUARE,
+        color: COLOR,
+        occupancy: Bitboard,
+    ) -> Bitboard {
+        let occupancy = occupancy.bits();
 
-        // get occupancy for sliding pieces
-        let occupancy = self.occupancy().bits();
+        match piece_type {
+            PieceType::PAWN => self.lookup_table.get_pawn_attacks(square, color),
+            PieceType::KNIGHT => self.lookup_table.get_knight_moves(square, color),
+            PieceType::BISHOP => self.lookup_table.get_bishop_moves(square, color, occupancy),
+            PieceType::ROOK => self.lookup_table.get_rook_moves(square, color, occupancy),
+            PieceType::QUEEN => self.lookup_table.get_queen_moves(square, color, occupancy),
+            PieceType::KING => self.lookup_table.get_king_moves(square, color),
+            PieceType::EMPTY => panic!("Cannot generate moves for empty square"),
+        }
+    }
+
+    // single and double pushes for a pawn on square, both stopped by any piece in the way
+    pub fn pawn_pushes(&self, square: SQUARE, color: COLOR) -> Bitboard {
+        let occupancy = self.occupancy();
+
+        match square.forward(color) {
+            Some(single) if !occupancy.is_set(single.index()) => {
+                self.lookup_table.get_pawn_moves(square, color)
+                    & !self.lookup_table.get_pawn_attacks(square, color)
+                    & !occupancy
+            }
+            _ => Bitboard::default(),
+        }
+    }
+
+    // generates moves for every piece of this kind whose target is in targets (pawn pushes and
+    // en passant are masked by the same bitboard, so the caller decides which of them it wants)
+    fn generate_moves_for_piece(&self, piece: PIECE, targets: Bitboard, moves: &mut MoveList) {
+        let occupancy = self.occupancy();
 
         // get the corresponding bitboard for the piece
-        let mut piece_bb = piece.piece_bb(&self);
+        let mut piece_bb = self.occupancy_of_piece(piece);
 
         let color = piece.color().unwrap();
         let piece_type = piece.piece_type();
 
+        let enemies = self.occupancy_of_color(color.opposite());
+        let en_passant = Bitboard::new(self.en_passant_target.map_or(0, |s| s.bits()));
+
         // now for each source square index (each piece of that type on the board, or each bit on the bitboard)
         while let Some(source_idx) = piece_bb.pop_lsb() {
             let source_square = SQUARE::from(source_idx);
 
             // 1. get the move bitboard (bb of target squares) for the piece at square
             let mut move_bb = match piece_type {
-                PieceType::PAWN => self.lookup_table.get_pawn_moves(source_square, color),
-                PieceType::KNIGHT => self.lookup_table.get_knight_moves(source_square, color),
-                PieceType::BISHOP => {
-                    self.lookup_table
-                        .get_bishop_moves(source_square, color, occupancy)
-                }
-                PieceType::ROOK => {
-                    self.lookup_table
-                        .get_rook_moves(source_square, color, occupancy)
+                PieceType::PAWN => {
+                    self.pawn_pushes(source_square, color)
+                        | self.attacks_from(piece_type, source_square, color, occupancy)
+                            & (enemies | en_passant)
                 }
-                PieceType::QUEEN => {
-                    self.lookup_table
-                        .get_queen_moves(source_square, color, occupancy)
-                }
-                PieceType::KING => self.lookup_table.get_king_moves(source_square, color),
-                PieceType::EMPTY => panic!("Cannot generate moves for empty square"),
-            };
+                _ => self.attacks_from(piece_type, source_square, color, occupancy),
+            } & targets;
 
             // 2. Iterate over the target squares from the move bitboard
             while let Some(target_idx) = move_bb.pop_lsb() {
                 let target_square = SQUARE::from(target_idx);
-                let is_pawn_promotion =
-                    piece_type == PieceType::PAWN && target_square.is_pawn_promote(color);
-                let mut is_en_passant = false;
-
-                let capture = match self.piece_at_square(target_square) {
-                    PIECE::Empty => None,
-                    target_piece => {
-                        if target_piece.color() == Some(color.opposite()) {
-                            Some(target_piece)
-                        } else {
-                            None
-                        }
-                    }
-                };
+                let capture = enemies.is_set(target_idx);
 
-                // 4. If pawn, do some special checks
-                if piece_type == PieceType::PAWN {
-                    is_en_passant = match color {
-                        COLOR::WHITE => {
-                            Some(target_square) == self.en_passant_target
-                                && source_square.rank() == RANK::Rank5
-                                && target_square.rank() == RANK::Rank6
-                        }
-                        COLOR::BLACK => {
-                            Some(target_square) == self.en_passant_target
-                                && source_square.rank() == RANK::Rank4
-                                && target_square.rank() == RANK::Rank3
-                        }
-                    };
+                // 3. Pawns get special flags for promotions, en passant and double pushes
+                if piece_type == PieceType::PAWN && target_square.is_pawn_promote(color) {
+                    for promotion_option in PROMOTION_OPTIONS.iter() {
+                        let flag = MoveFlag::promotion_to(*promotion_option, capture);
+                        moves.push(Move::new(source_square, target_square, flag));
+                    }
+                    continue;
                 }
 
-                // 5. Add the move to the list. If pawn promotion, add all promotion options instead
-                match is_pawn_promotion {
-                    true => {
-                        for promotion_option in PROMOTION_OPTIONS.iter() {
-                            moves.push(Move::new(
-                                source_square,
-                                target_square,
-                                Some(*promotion_option),
-                                None,
-                                capture,
-                                false,
-                            ));
-                        }
+                let flag = match piece_type {
+                    _ if capture => MoveFlag::Capture,
+                    PieceType::PAWN if en_passant.is_set(target_idx) => MoveFlag::EnPassant,
+                    PieceType::PAWN if (source_square.rank() - target_square.rank()).abs() == 2 => {
+                        MoveFlag::DoublePawnPush
                     }
-                    false => moves.push(Move::new(
-                        source_square,
-                        target_square,
-                        None,
-                        None,
-                        capture,
-                        is_en_passant,
-                    )),
-                }
+                    _ => MoveFlag::Quiet,
+                };
+
+                moves.push(Move::new(source_square, target_square, flag));
             }
+        }
+    }
 
-            // 6. If king, add castling moves (add all now, filter later)
-            if piece_type == PieceType::KING {
-                let target_squares = [SQUARE::G1, SQUARE::C1, SQUARE::G8, SQUARE::C8];
-                let castles = [
-                    CASTLE::WhiteKingside,
-                    CASTLE::WhiteQueenside,
-                    CASTLE::BlackKingside,
-                    CASTLE::BlackQueenside,
-                ];
-
-                for i in 0..4 {
-                    moves.push(Move::new(
-                        source_square,
-                        target_squares[i],
-                        None,
-                        Some(castles[i]),
-                        None,
-                        false,
-                    ));
-                }
+    // castles whose rook is still in place and whose path is clear (attacked squares are checked
+    // later). The move is encoded as the king capturing its own rook, which also covers chess960
+    // starting positions where the king already stands on its target square
+    pub(crate) fn generate_castles(&self, king_square: SQUARE, color: COLOR, moves: &mut MoveList) {
+        for kingside in [true, false] {
+            let castle = CASTLE::of_color(color, kingside);
+
+            let rook_square = match self.castling_rights.rook_square(castle) {
+                Some(rook_square) => rook_square,
+                None => continue,
+            };
+
+            let can_castle = king_square.rank() == castle.back_rank()
+                && (rook_square.file() > king_square.file()) == kingside
+                && self.piece_at_square(rook_square) == PieceType::ROOK.of_color(color)
+                && (self.castling_path(castle, king_square, rook_square) & self.occupancy())
+                    .empty();
+
+            if can_castle {
+                let flag = match kingside {
+                    true => MoveFlag::KingCastle,
+                    false => MoveFlag::QueenCastle,
+                };
+                moves.push(Move::new(king_square, rook_square, flag));
             }
         }
+    }
+
+    // every square the king and rook cross or land on, apart from the two squares they start on
+    pub fn castling_path(
+        &self,
+        castle: CASTLE,
+        king_square: SQUARE,
+        rook_square: SQUARE,
+    ) -> Bitboard {
+        let king_target = castle.king_target();
+        let rook_target = castle.rook_target();
+
+        let path = self.lookup_table.get_between(king_square, king_target)
+            | self.lookup_table.get_between(rook_square, rook_target)
+            | Bitboard::new(king_target.bits() | rook_target.bits());
 
+        path & !Bitboard::new(king_square.bits() | rook_square.bits())
+    }
+
+    // all moves that follow piece movement rules, without checking if they leave the king in check
+    pub fn generate_pseudo_moves_for_color(&self, color: COLOR) -> MoveList {
+        self.generate_pseudo_moves(color, GenType::All)
+    }
+
+    // pseudo-legal captures, en passant and promotions
+    pub fn generate_captures(&self, color: COLOR) -> MoveList {
+        self.generate_pseudo_moves(color, GenType::Captures)
+    }
+
+    // pseudo-legal non-capturing, non-promoting moves including castles
+    pub fn generate_quiets(&self, color: COLOR) -> MoveList {
+        self.generate_pseudo_moves(color, GenType::Quiets)
+    }
+
+    // pseudo-legal moves that could resolve a check, only meaningful while in check
+    pub fn generate_evasions(&self, color: COLOR) -> MoveList {
+        self.generate_pseudo_moves(color, GenType::Evasions)
+    }
+
+    // pseudo-legal quiet moves that put the opponent in check
+    pub fn generate_quiet_checks(&self, color: COLOR) -> MoveList {
+        self.generate_pseudo_moves(color, GenType::QuietChecks)
+    }
+
+    pub fn generate_pseudo_moves(&self, color: COLOR, gen_type: GenType) -> MoveList {
+        let mut moves = MoveList::new();
+
+        let occupancy = self.occupancy();
+        let enemies = self.occupancy_of_color(color.opposite());
+        let en_passant = Bitboard::new(self.en_passant_target.map_or(0, |s| s.bits()));
+        let promotion_rank = Bitboard::new(match color {
+            COLOR::WHITE => RANK::Rank8.bits(),
+            COLOR::BLACK => RANK::Rank1.bits(),
+        });
+
+        // kings are never captured, and we never land on our own pieces
+        let not_own =
+            !self.occupancy_of_color(color) & !self.pieces_of(color.opposite(), PieceType::KING);
+
+        // (pawn targets, other piece targets, king targets)
+        let (pawn_targets, piece_targets, king_targets) = match gen_type {
+            GenType::All => (not_own, not_own, not_own),
+            GenType::Captures => (
+                (enemies | en_passant | promotion_rank) & not_own,
+                enemies & not_own,
+                enemies & not_own,
+            ),
+            GenType::Quiets | GenType::QuietChecks => (
+                !occupancy & !en_passant & !promotion_rank,
+                !occupancy,
+                !occupancy,
+            ),
+            GenType::Evasions => {
+                let king_square = self.king_square(color);
+                let checkers = self.attackers_to(king_square, occupancy) & enemies;
+
+                // in double check only the king can move
+                let blocks = match checkers.count() {
+                    1 => {
+                        let checker = SQUARE::from_bits(checkers.bits());
+                        self.lookup_table.get_between(king_square, checker) | checkers
+                    }
+                    _ => Bitboard::default(),
+                };
+
+                // a checking pawn that just double pushed can also be taken en passant
+                let en_passant_evasion = match self.en_passant_target {
+                    Some(target)
+                        if target.forward(color.opposite()).map(|s| s.bits())
+                            == Some(checkers.bits()) =>
+                    {
+                        en_passant
+                    }
+                    _ => Bitboard::default(),
+                };
+
+                (blocks | en_passant_evasion, blocks, not_own)
+            }
+        };
+
+        self.generate_moves_for_piece(PieceType::PAWN.of_color(color), pawn_targets, &mut moves);
+        for piece_type in [
+            PieceType::KNIGHT,
+            PieceType::BISHOP,
+            PieceType::ROOK,
+            PieceType::QUEEN,
+        ] {
+            self.generate_moves_for_piece(piece_type.of_color(color), piece_targets, &mut moves);
+        }
+        self.generate_moves_for_piece(PieceType::KING.of_color(color), king_targets, &mut moves);
+
+        // castles are quiet king moves whose path is clear (attacked squares are checked later)
+        if gen_type == GenType::All
+            || gen_type == GenType::Quiets
+            || gen_type == GenType::QuietChecks
+        {
+            self.generate_castles(self.king_square(color), color, &mut moves);
+        }
+
+        if gen_type == GenType::QuietChecks {
+            moves.retain(|m| self.gives_check(m));
+        }
+
+        moves
+    }
+
+    pub fn generate_moves_for_color(&self, color: COLOR) -> MoveList {
+        let mut moves = self.generate_pseudo_moves_for_color(color);
+        moves.retain(|m| self.is_legal(m));
+        moves
+    }
+
+    // legal moves that give check: captures and promotions that do, then the quiet checks
+    pub fn generate_checks(&self, color: COLOR) -> MoveList {
+        let mut moves = self.generate_captures(color);
+        moves.retain(|m| self.gives_check(m));
+
+        for m in self.generate_quiet_checks(color).iter() {
+            moves.push(*m);
+        }
+        moves.retain(|m| self.is_legal(m));
         moves
     }
 
-    pub fn generate_moves_for_color(&self, color: COLOR) -> Vec<Move> {
-        vec![
-            self.generate_moves_for_piece(PieceType::PAWN.of_color(color)),
-            self.generate_moves_for_piece(PieceType::KNIGHT.of_color(color)),
-            self.generate_moves_for_piece(PieceType::BISHOP.of_color(color)),
-            self.generate_moves_for_piece(PieceType::ROOK.of_color(color)),
-            self.generate_moves_for_piece(PieceType::QUEEN.of_color(color)),
-            self.generate_moves_for_piece(PieceType::KING.of_color(color)),
-        ]
-        .into_iter()
-        .flatten()
-        .filter(|m| self.is_move_valid(*m))
-        .collect()
+    // whether the side to move has any legal move, stopping at the first one found. King moves go
+    // first since they are cheap to generate and the likeliest way out of a check
+    pub fn has_legal_move(&self) -> bool {
+        let color = self.to_move;
+        let king_square = self.king_square(color);
+        let not_own =
+            !self.occupancy_of_color(color) & !self.pieces_of(color.opposite(), PieceType::KING);
+
+        let mut moves = MoveList::new();
+        self.generate_moves_for_piece(PieceType::KING.of_color(color), not_own, &mut moves);
+        if moves.iter().any(|m| self.is_legal(*m)) {
+            return true;
+        }
+
+        match self.checkers().count() {
+            // one piece type at a time, castles last as they are rarely the only move
+            0 => {
+                for piece_type in [
+                    PieceType::PAWN,
+                    PieceType::KNIGHT,
+                    PieceType::BISHOP,
+                    PieceType::ROOK,
+                    PieceType::QUEEN,
+                ] {
+                    moves.clear();
+                    self.generate_moves_for_piece(piece_type.of_color(color), not_own, &mut moves);
+                    if moves.iter().any(|m| self.is_legal(*m)) {
+                        return true;
+                    }
+                }
+
+                moves.clear();
+                self.generate_castles(king_square, color, &mut moves);
+                moves.iter().any(|m| self.is_legal(*m))
+            }
+            1 => self
+                .generate_evasions(color)
+                .iter()
+                .any(|m| m.source() != king_square && self.is_legal(*m)),
+            // only the king can answer a double check
+            _ => false,
+        }
     }
 }
@@ -1,223 +1,448 @@
-use crate::{bitboard::*, board::*, enums::*, helpers::*, r#move::*};
+use crate::{bitboard::*, board::*, enums::*, r#move::*};
 
 impl<'a> Board<'a> {
     // checks if a move is valid given board configuration
     pub fn is_move_valid(&self, m: Move) -> bool {
-        let source = m.source;
-        let target = m.target;
-        let castling = m.castling;
+        self.is_pseudo_legal(m) && self.is_legal(m)
+    }
+
+    // checks that a move could have come from the move generator for the side to move.
+    // moves pulled out of a hash table or killer slot may belong to a different position,
+    // so they have to pass this before they are made
+    pub fn is_pseudo_legal(&self, m: Move) -> bool {
+        if m.is_null() {
+            return false;
+        }
+
+        let source = m.source();
+        let target = m.target();
+        let flag = m.flag();
+        let color = self.to_move;
 
-        // Get details of the piece that is moving
         let source_piece = self.piece_at_square(source);
         let target_piece = self.piece_at_square(target);
 
-        let source_color = source_piece.color().expect("source piece is empty");
-        let target_color = target_piece.color();
+        // 1. Must move one of our own pieces
+        if !source_piece.is_color(color) {
+            return false;
+        }
 
-        let target_empty = target_piece == PIECE::Empty;
-        let is_capture = Some(source_color.opposite()) == target_color;
-        let friendly_capture = Some(source_color) == target_color;
+        // 2. Castles (king takes own rook) have to be one the generator would produce right now
+        if flag.is_castle() {
+            let mut castles = MoveList::new();
+            if source_piece.is_king() {
+                self.generate_castles(source, color, &mut castles);
+            }
+            return castles.contains(m);
+        }
 
-        let rank_diff = (source.rank() - target.rank()).abs();
-        let file_diff = (source.file() - target.file()).abs();
+        // 3. Check for friendly capture or king capture
+        if target_piece.is_color(color) || target_piece.is_king() {
+            return false;
+        }
 
-        let rank_unchanged = rank_diff == 0;
-        let file_unchanged = file_diff == 0;
+        // 4. The capture flag has to agree with the board (en passant lands on an empty square)
+        let is_capture = target_piece.is_opposite_color(color);
+        if flag.is_capture() != (is_capture || flag == MoveFlag::EnPassant) {
+            return false;
+        }
 
-        let pawn_move = source_piece.piece_type() == PieceType::PAWN;
-        let pawn_single_push = pawn_move && rank_diff == 1 && file_unchanged;
-        let pawn_double_push = pawn_move && rank_diff == 2 && file_unchanged;
-        let pawn_diagonal_move = pawn_move && file_diff == 1 && rank_diff == 1;
+        let rank_diff = (source.rank() - target.rank()).abs();
 
-        let pawn_promoted =
-            pawn_move && (target.rank() == RANK::Rank1 || target.rank() == RANK::Rank8);
+        // 5. Pawns: pushes onto empty squares, diagonal captures, en passant and promotions
+        if source_piece.is_pawn() {
+            if flag.is_promotion() != target.is_pawn_promote(color) {
+                return false;
+            }
 
-        // 0. Assert source piece is not empty
-        assert_ne!(source_piece, PIECE::Empty);
+            let attacks = self.attacks_from(PieceType::PAWN, source, color, self.occupancy());
+            let pushes = self.pawn_pushes(source, color);
 
-        // 1. Check for friendly capture or king capture
-        let king_capture = target_piece.piece_type() == PieceType::KING;
-        if friendly_capture || king_capture {
-            return false;
+            return match flag {
+                MoveFlag::EnPassant => {
+                    Some(target) == self.en_passant_target && attacks.is_set(target.index())
+                }
+                MoveFlag::DoublePawnPush => pushes.is_set(target.index()) && rank_diff == 2,
+                _ if is_capture => attacks.is_set(target.index()),
+                _ => pushes.is_set(target.index()) && rank_diff == 1,
+            };
         }
 
-        // 2. Check if pawn single push is legal - can't capture forward
-        if pawn_single_push && is_capture {
-            return false;
+        // 6. Everything else moves along its attack set with a plain quiet or capture flag
+        match flag {
+            MoveFlag::Quiet | MoveFlag::Capture => self
+                .attacks_from(source_piece.piece_type(), source, color, self.occupancy())
+                .is_set(target.index()),
+            _ => false,
         }
+    }
 
-        // 3. Check if pawn double push is legal
-        if pawn_double_push {
-            let intermediate_square = match source_color {
-                COLOR::WHITE => source.north().unwrap(),
-                COLOR::BLACK => source.south().unwrap(),
-            };
-            let intermediate_piece = self.piece_at_square(intermediate_square);
-            let intermediate_empty = intermediate_piece == PIECE::Empty;
+    // checks a pseudo-legal move doesn't castle out of or through check or leave the king in check
+    pub fn is_legal(&self, m: Move) -> bool {
+        let source_color = self
+            .piece_at_square(m.source())
+            .color()
+            .expect("source piece is empty");
 
-            // if target or intermediate square is occupied, return false
-            if !target_empty || !intermediate_empty {
+        // 1. Check castling
+        if let Some(castle) = m.castling() {
+            // No castling out of check
+            if self.in_check(source_color) {
                 return false;
             }
+
+            // the king can't pass through or land on an attacked square
+            let king_target = castle.king_target();
+            let mut king_path = self.lookup_table.get_between(m.source(), king_target)
+                | Bitboard::new(king_target.bits());
+
+            while let Some(index) = king_path.pop_lsb() {
+                if self.square_under_attack(SQUARE::from(index), source_color) {
+                    return false;
+                }
+            }
         }
 
-        // 4. Check if the pawn diagonal move is legal (only possible if capture or en passant)
-        if pawn_diagonal_move && !is_capture && self.en_passant_target.is_none() {
-            return false;
+        // 2. Invalidate moves where king is under check after move
+        let mut board_copy = self.clone();
+        board_copy.make_move_unchecked(m);
+
+        !board_copy.in_check(source_color)
+    }
+
+    // the same checks as is_pseudo_legal and is_legal, but saying which one failed, for anyone
+    // who has to tell a player why their move was rejected. Slower, so not meant for search
+    pub fn validate_move(&self, m: Move) -> Result<(), IllegalMoveReason> {
+        if m.is_null() {
+            return Err(IllegalMoveReason::NullMove);
         }
 
-        // 5. Check for en passant
-        if pawn_diagonal_move && !is_capture && self.en_passant_target.is_some() {
-            let en_passant_target = self
-                .en_passant_target
-                .expect("en passant move but en passant target not set");
+        let source = m.source();
+        let target = m.target();
+        let color = self.to_move;
 
-            // make sure move target and the en passant target are the same
-            if target != en_passant_target {
-                return false;
+        let source_piece = self.piece_at_square(source);
+        let target_piece = self.piece_at_square(target);
+        let occupancy = self.occupancy();
+
+        // 1. Must move one of our own pieces
+        match source_piece.color() {
+            None => return Err(IllegalMoveReason::EmptySquare(source)),
+            Some(source_color) if source_color != color => {
+                return Err(IllegalMoveReason::NotYourTurn(source))
             }
+            Some(_) => {}
         }
 
-        // 7. Check for castling
-        if castling.is_some() {
-            // No castling out of check
-            if self.in_check(source_color) {
-                return false;
+        // 2. Castles: the right, a clear path, and no attacked square on the king's way
+        if m.is_castle() {
+            let castle = match m.castling() {
+                Some(castle) if source_piece.is_king() && castle.color() == color => castle,
+                _ => return Err(IllegalMoveReason::InvalidMovement),
+            };
+            let rook_square = self
+                .castling_rights
+                .rook_square(castle)
+                .ok_or(IllegalMoveReason::NoCastlingRights(castle))?;
+            if rook_square != target
+                || (rook_square.file() > source.file()) != castle.is_kingside()
+                || target_piece != PieceType::ROOK.of_color(color)
+            {
+                return Err(IllegalMoveReason::InvalidMovement);
             }
 
-            let wkc_blocked = (WKC_BITS & self.occupancy().bits()) != 0;
-            let wqc_blocked = (WQC_BITS & self.occupancy().bits()) != 0;
-            let bkc_blocked = (BKC_BITS & self.occupancy().bits()) != 0;
-            let bqc_blocked = (BQC_BITS & self.occupancy().bits()) != 0;
-
-            let wkc_attacked = WKC_SQUARES
-                .iter()
-                .any(|s| self.square_under_attack(*s, source_color));
-            let wqc_attacked = WQC_SQUARES
-                .iter()
-                .take(2)
-                .any(|s| self.square_under_attack(*s, source_color));
-            let bkc_attacked = BKC_SQUARES
-                .iter()
-                .any(|s| self.square_under_attack(*s, source_color));
-            let bqc_attacked = BQC_SQUARES
-                .iter()
-                .take(2)
-                .any(|s| self.square_under_attack(*s, source_color));
-
-            match castling {
-                Some(CASTLE::WhiteKingside) => {
-                    return source_color == COLOR::WHITE
-                        && !wkc_blocked
-                        && !wkc_attacked
-                        && self.castling_rights.white_kingside;
-                }
-                Some(CASTLE::WhiteQueenside) => {
-                    return source_color == COLOR::WHITE
-                        && !wqc_blocked
-                        && !wqc_attacked
-                        && self.castling_rights.white_queenside;
-                }
-                Some(CASTLE::BlackKingside) => {
-                    return source_color == COLOR::BLACK
-                        && !bkc_blocked
-                        && !bkc_attacked
-                        && self.castling_rights.black_kingside;
-                }
-                Some(CASTLE::BlackQueenside) => {
-                    return source_color == COLOR::BLACK
-                        && !bqc_blocked
-                        && !bqc_attacked
-                        && self.castling_rights.black_queenside;
+            let blockers = self.castling_path(castle, source, rook_square) & occupancy;
+            if let Some(blocker) = self.nearest(blockers, source) {
+                return Err(IllegalMoveReason::CastlingBlocked(blocker));
+            }
+
+            if self.in_check(color) {
+                return Err(IllegalMoveReason::CastlingOutOfCheck);
+            }
+
+            let king_target = castle.king_target();
+            let mut king_path = self.lookup_table.get_between(source, king_target)
+                | Bitboard::new(king_target.bits());
+            let mut attacked = Bitboard::default();
+            while let Some(index) = king_path.pop_lsb() {
+                if self.square_under_attack(SQUARE::from(index), color) {
+                    attacked.set(index);
                 }
-                _ => {}
             }
+            if let Some(square) = self.nearest(attacked, source) {
+                return Err(IllegalMoveReason::CastlingThroughCheck(square));
+            }
+        } else {
+            // 3. Captures have to take an enemy piece other than the king
+            if target_piece.is_color(color) {
+                return Err(IllegalMoveReason::FriendlyCapture(target));
+            }
+            if target_piece.is_king() {
+                return Err(IllegalMoveReason::KingCapture(target));
+            }
+
+            // 4. The piece has to be able to get there
+            self.validate_movement(m)?;
         }
 
-        // 6. Invalidate moves where king is under check after move
+        // 5. And the king must be safe afterwards
         let mut board_copy = self.clone();
-        board_copy.make_move(m);
+        board_copy.make_move_unchecked(m);
+        if !board_copy.in_check(color) {
+            return Ok(());
+        }
 
-        if board_copy.in_check(source_color) {
-            return false;
+        if source_piece.is_king() {
+            return Err(IllegalMoveReason::KingMovesIntoCheck);
         }
 
-        true
+        let pins = self.pinned(color);
+        if pins.is_pinned(source) && !pins.ray(source).is_set(target.index()) {
+            let pinner = pins.ray(source) & self.occupancy_of_color(color.opposite());
+            return Err(IllegalMoveReason::PiecePinned(SQUARE::from_bits(
+                pinner.bits(),
+            )));
+        }
+
+        Err(IllegalMoveReason::KingLeftInCheck)
+    }
+
+    // the movement rules of the piece on the source square for a move that isn't a castle
+    fn validate_movement(&self, m: Move) -> Result<(), IllegalMoveReason> {
+        let source = m.source();
+        let target = m.target();
+        let flag = m.flag();
+        let color = self.to_move;
+        let piece_type = self.piece_at_square(source).piece_type();
+        let occupancy = self.occupancy();
+
+        let is_capture = self.piece_at_square(target).is_opposite_color(color);
+        let rank_diff = (source.rank() - target.rank()).abs();
+
+        // 1. Pawns push straight ahead onto empty squares and capture diagonally
+        if piece_type == PieceType::PAWN {
+            let attacks = self.lookup_table.get_pawn_attacks(source, color);
+            let pushes = self.lookup_table.get_pawn_moves(source, color) & !attacks;
+
+            if !(attacks | pushes).is_set(target.index()) {
+                return Err(IllegalMoveReason::InvalidMovement);
+            }
+            if flag.is_promotion() != target.is_pawn_promote(color) {
+                return Err(IllegalMoveReason::FlagMismatch);
+            }
+
+            if flag == MoveFlag::EnPassant {
+                return match Some(target) == self.en_passant_target
+                    && attacks.is_set(target.index())
+                {
+                    true => Ok(()),
+                    false => Err(IllegalMoveReason::NoEnPassant),
+                };
+            }
+
+            if attacks.is_set(target.index()) {
+                return match (is_capture, flag.is_capture()) {
+                    (true, true) => Ok(()),
+                    (false, false) => Err(IllegalMoveReason::InvalidMovement),
+                    _ => Err(IllegalMoveReason::FlagMismatch),
+                };
+            }
+
+            let path = self.lookup_table.get_between(source, target) | Bitboard::new(target.bits());
+            if let Some(blocker) = self.nearest(path & occupancy, source) {
+                return Err(IllegalMoveReason::PathBlocked(blocker));
+            }
+            if flag.is_capture() || (flag == MoveFlag::DoublePawnPush) != (rank_diff == 2) {
+                return Err(IllegalMoveReason::FlagMismatch);
+            }
+            return Ok(());
+        }
+
+        // 2. Everything else moves along its attack set, sliders stopping at the first piece
+        let reach = self.attacks_from(piece_type, source, color, Bitboard::default());
+        if !reach.is_set(target.index()) {
+            return Err(IllegalMoveReason::InvalidMovement);
+        }
+
+        let blockers = self.lookup_table.get_between(source, target) & occupancy;
+        if let Some(blocker) = self.nearest(blockers, source) {
+            return Err(IllegalMoveReason::PathBlocked(blocker));
+        }
+
+        match (flag, is_capture) {
+            (MoveFlag::Quiet, false) | (MoveFlag::Capture, true) => Ok(()),
+            _ => Err(IllegalMoveReason::FlagMismatch),
+        }
+    }
+
+    // the one of squares closest to from, if there are any
+    fn nearest(&self, squares: Bitboard, from: SQUARE) -> Option<SQUARE> {
+        let mut squares = squares;
+        let mut nearest = None;
+
+        while let Some(index) = squares.pop_lsb() {
+            let square = SQUARE::from(index);
+            let closer = nearest.is_none_or(|other| {
+                self.lookup_table.get_distance(from, square)
+                    < self.lookup_table.get_distance(from, other)
+            });
+            if closer {
+                nearest = Some(square);
+            }
+        }
+
+        nearest
+    }
+
+    // whether the move puts the opponent of the moving side in check
+    pub fn gives_check(&self, m: Move) -> bool {
+        let piece = self.piece_at_square(m.source());
+        let color = piece.color().expect("source piece is empty");
+        let king_square = self.king_square(color.opposite());
+
+        // 1. A plain move either checks from its target, or uncovers a slider behind it, which
+        // needs the source on a line through the king that the move leaves
+        if matches!(
+            m.flag(),
+            MoveFlag::Quiet | MoveFlag::DoublePawnPush | MoveFlag::Capture
+        ) {
+            let occupancy = (self.occupancy() & !Bitboard::new(m.source().bits()))
+                | Bitboard::new(m.target().bits());
+
+            if self
+                .attacks_from(piece.piece_type(), m.target(), color, occupancy)
+                .is_set(king_square.index())
+            {
+                return true;
+            }
+
+            return self.lookup_table.get_line(king_square, m.source()).any()
+                && !self
+                    .lookup_table
+                    .aligned(king_square, m.source(), m.target())
+                && (self.attackers_to(king_square, occupancy) & self.occupancy_of_color(color))
+                    .any();
+        }
+
+        // 2. Castles, en passant and promotions move or remove more than one piece, so they are
+        // played out
+        let mut board_copy = self.clone();
+        board_copy.make_move_unchecked(m);
+
+        board_copy.in_check(color.opposite())
     }
 
     pub fn in_check(&self, color: COLOR) -> bool {
-        let king_square = SQUARE::from_bits(match color {
-            COLOR::WHITE => self.white_king.bits(),
-            COLOR::BLACK => self.black_king.bits(),
-        });
+        let king_square = self.king_square(color);
 
         self.square_under_attack(king_square, color)
     }
 
     // returns is square under attack by the opposite color
     pub fn square_under_attack(&self, square: SQUARE, color: COLOR) -> bool {
-        // We do this by placing each of pawn, knight, bishop, rook, queen, and king on the square of interest and computing attacks
-        // If any of the target squares is the same piece type as the attacking piece, the king is under attack
-
-        // Start with pawns, but remove single and double push moves
-        let pawn_moves = self.lookup_table.get_pawn_moves(square, color);
-
-        // for pawns, remove single and double push moves from attacks
-        let pawn_single_push = Bitboard::new(match color {
-            COLOR::WHITE => north(square.bits()).unwrap_or(0),
-            COLOR::BLACK => south(square.bits()).unwrap_or(0),
-        });
-        let pawn_double_push = Bitboard::new(match color {
-            COLOR::WHITE => north_north(square.bits()).unwrap_or(0),
-            COLOR::BLACK => south_south(square.bits()).unwrap_or(0),
-        });
-
-        // Now we can compute only diagonal pawn moves
-        let pawn_attacks = pawn_moves & !(pawn_single_push | pawn_double_push);
-
-        // rest are straightforward
-        let knight_attacks = self.lookup_table.get_knight_moves(square, color);
-        let bishop_attacks =
-            self.lookup_table
-                .get_bishop_moves(square, color, self.occupancy().bits());
-        let rook_attacks = self
-            .lookup_table
-            .get_rook_moves(square, color, self.occupancy().bits());
-        let queen_attacks =
-            self.lookup_table
-                .get_queen_moves(square, color, self.occupancy().bits());
-        let king_attacks = self.lookup_table.get_king_moves(square, color);
-
-        // if any attacks are on opposite color pieces of same piecetype, the square is under attack
-        let under_pawn_attack = (pawn_attacks
-            & self.occupancy_of_piece(PieceType::PAWN.of_color(color.opposite())))
-        .any();
-
-        let under_knight_attack = (knight_attacks
-            & self.occupancy_of_piece(PieceType::KNIGHT.of_color(color.opposite())))
-        .any();
-
-        let under_bishop_attack = (bishop_attacks
-            & self.occupancy_of_piece(PieceType::BISHOP.of_color(color.opposite())))
-        .any();
-
-        let under_rook_attack = (rook_attacks
-            & self.occupancy_of_piece(PieceType::ROOK.of_color(color.opposite())))
-        .any();
-
-        let under_queen_attack = (queen_attacks
-            & self.occupancy_of_piece(PieceType::QUEEN.of_color(color.opposite())))
-        .any();
-
-        let under_king_attack = (king_attacks
-            & self.occupancy_of_piece(PieceType::KING.of_color(color.opposite())))
-        .any();
-
-        return under_pawn_attack
-            || under_knight_attack
-            || under_bishop_attack
-            || under_rook_attack
-            || under_queen_attack
-            || under_king_attack;
+        (self.attackers_to(square, self.occupancy()) & self.occupancy_of_color(color.opposite()))
+            .any()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, STARTING_FEN};
+    use crate::enums::*;
+    use crate::lookup_table::LookupTable;
+    use crate::r#move::Move;
+    use IllegalMoveReason::*;
+    use MoveFlag::*;
+    use SQUARE::*;
+
+    fn reason(fen: &str, m: Move) -> Result<(), IllegalMoveReason> {
+        let lookup_table = LookupTable::new();
+        Board::from_fen(fen, &lookup_table).validate_move(m)
+    }
+
+    #[test]
+    fn wrong_pieces_and_targets() {
+        assert_eq!(reason(STARTING_FEN, Move::NULL), Err(NullMove));
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(E3, E4, Quiet)),
+            Err(EmptySquare(E3))
+        );
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(E7, E5, DoublePawnPush)),
+            Err(NotYourTurn(E7))
+        );
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(D1, D2, Capture)),
+            Err(FriendlyCapture(D2))
+        );
+        assert_eq!(
+            reason(
+                "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+                Move::new(E7, E8, Capture)
+            ),
+            Err(KingCapture(E8))
+        );
+    }
+
+    #[test]
+    fn movement() {
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(E2, E5, Quiet)),
+            Err(InvalidMovement)
+        );
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(F1, C4, Quiet)),
+            Err(PathBlocked(E2))
+        );
+        // a double push has to say so
+        assert_eq!(
+            reason(STARTING_FEN, Move::new(E2, E4, Quiet)),
+            Err(FlagMismatch)
+        );
+        assert_eq!(
+            reason(
+                "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1",
+                Move::new(E5, D6, EnPassant)
+            ),
+            Err(NoEnPassant)
+        );
+    }
+
+    #[test]
+    fn castles() {
+        let castle = Move::new(E1, H1, KingCastle);
+        assert_eq!(reason(STARTING_FEN, castle), Err(CastlingBlocked(F1)));
+        assert_eq!(
+            reason("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1", castle),
+            Err(NoCastlingRights(CASTLE::WhiteKingside))
+        );
+        assert_eq!(
+            reason("r3k2r/8/8/8/8/4r3/8/R3K2R w KQkq - 0 1", castle),
+            Err(CastlingOutOfCheck)
+        );
+        assert_eq!(
+            reason("r3k2r/8/8/8/8/5r2/8/R3K2R w KQkq - 0 1", castle),
+            Err(CastlingThroughCheck(F1))
+        );
+    }
+
+    #[test]
+    fn king_safety() {
+        assert_eq!(
+            reason(
+                "4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1",
+                Move::new(E2, C3, Quiet)
+            ),
+            Err(PiecePinned(E7))
+        );
+        assert_eq!(
+            reason("4k3/4r3/8/8/8/8/8/3K4 w - - 0 1", Move::new(D1, E1, Quiet)),
+            Err(KingMovesIntoCheck)
+        );
+        // the knight doesn't answer the rook's check
+        assert_eq!(
+            reason("4k3/4r3/8/8/8/8/8/N3K3 w - - 0 1", Move::new(A1, B3, Quiet)),
+            Err(KingLeftInCheck)
+        );
     }
 }
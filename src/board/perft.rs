@@ -1,5 +1,5 @@
 use crate::board::Board;
-use crate::enums::ENDGAME;
+use crate::r#move::*;
 use std::io::Write;
 
 impl<'a> Board<'a> {
@@ -8,6 +8,7 @@ impl<'a> Board<'a> {
         depth: u8,
         max_depth: u8,
         node_only: bool,
+        staged: bool,
     ) -> (u64, u64, u64, u64, u64, u64, u64) {
         let mut nodes = 0;
         let mut captures = 0;
@@ -21,7 +22,15 @@ impl<'a> Board<'a> {
             return (1, 0, 0, 0, 0, 0, 0);
         }
 
-        let moves = self.generate_moves_for_color(self.to_move);
+        let moves = match staged {
+            true => self.generate_staged_moves(),
+            false => self.generate_moves_for_color(self.to_move),
+        };
+
+        // when only nodes are counted, the last ply's moves are counted without playing them
+        if node_only && depth == 1 {
+            return (moves.len() as u64, 0, 0, 0, 0, 0, 0);
+        }
 
         if depth == max_depth {
             print!("(0/{}) |>{}|\r", moves.len(), " ".repeat(moves.len()));
@@ -34,14 +43,16 @@ impl<'a> Board<'a> {
 
             if depth == 1 {
                 nodes += 1;
-                captures += (m.capture.is_some() | m.en_passant) as u64;
-                en_passants += m.en_passant as u64;
-                castles += m.castling.is_some() as u64;
-                promotions += m.promotion.is_some() as u64;
-                checks += board.in_check(board.to_move) as u64;
-                checkmates += (board.endgame() == Some(ENDGAME::Checkmate)) as u64;
+                captures += m.is_capture() as u64;
+                en_passants += m.is_en_passant() as u64;
+                castles += m.is_castle() as u64;
+                promotions += m.is_promotion() as u64;
+                let in_check = board.in_check(board.to_move);
+                checks += in_check as u64;
+                checkmates += (in_check && !board.has_legal_move()) as u64;
             } else {
-                let (n, c, en, ca, pro, ch, cm) = board.perft(depth - 1, max_depth, node_only);
+                let (n, c, en, ca, pro, ch, cm) =
+                    board.perft(depth - 1, max_depth, node_only, staged);
                 nodes += n;
                 captures += c;
                 en_passants += en;
@@ -68,4 +79,18 @@ impl<'a> Board<'a> {
             checkmates,
         )
     }
+
+    // legal moves collected through every stage of the move picker, which must
+    // add up to exactly the moves the full generator produces
+    fn generate_staged_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+
+        for m in MovePicker::new(self, Move::NULL, [Move::NULL; 2]) {
+            if self.is_legal(m) {
+                moves.push(m);
+            }
+        }
+
+        moves
+    }
 }
@@ -6,69 +6,32 @@ use crate::r#move::Move;
 impl<'a> Board<'a> {
     pub fn remove_piece(&mut self, index: usize) {
         let piece = self.piece_at_index(index);
-        match piece {
-            PIECE::WhitePawn => self.white_pawns.unset(index),
-            PIECE::WhiteKnight => self.white_knights.unset(index),
-            PIECE::WhiteBishop => self.white_bishops.unset(index),
-            PIECE::WhiteRook => {
-                self.white_rooks.unset(index);
-                match SQUARE::from(index) {
-                    SQUARE::A1 => self.castling_rights.set(CASTLE::WhiteQueenside, false),
-                    SQUARE::H1 => self.castling_rights.set(CASTLE::WhiteKingside, false),
-                    _ => {}
-                }
-            }
-            PIECE::WhiteQueen => self.white_queens.unset(index),
-
-            PIECE::BlackPawn => self.black_pawns.unset(index),
-            PIECE::BlackKnight => self.black_knights.unset(index),
-            PIECE::BlackBishop => self.black_bishops.unset(index),
-            PIECE::BlackRook => {
-                self.black_rooks.unset(index);
-                match SQUARE::from(index) {
-                    SQUARE::A8 => self.castling_rights.set(CASTLE::BlackQueenside, false),
-                    SQUARE::H8 => self.castling_rights.set(CASTLE::BlackKingside, false),
-                    _ => {}
-                }
-            }
-            PIECE::BlackQueen => self.black_queens.unset(index),
-
-            // temporarily remove the king from the board, will check for endgame conditions later
-            PIECE::WhiteKing => {
-                self.white_king.unset(index);
-            }
-
-            PIECE::BlackKing => {
-                self.black_king.unset(index);
-            }
-
-            PIECE::Empty => {
+        let color = match piece.color() {
+            Some(color) => color,
+            None => {
                 println!("board:\n{}", self);
                 panic!(
                     "Tried to remove empty piece at square {}",
                     SQUARE::from(index)
                 );
             }
+        };
+
+        self.pieces[color.index()][piece.piece_type().index()].unset(index);
+        self.color_occupancy[color.index()].unset(index);
+        self.mailbox[index] = PIECE::Empty;
+        self.key ^= Board::piece_key(piece, index);
+
+        // a castling rook leaving its square (moved or captured) loses that side's castling right
+        if piece.is_rook() {
+            self.castling_rights.remove_rook(color, SQUARE::from(index));
         }
     }
 
     pub fn add_piece(&mut self, index: usize, piece: PIECE) {
-        match piece {
-            PIECE::WhitePawn => self.white_pawns.set(index),
-            PIECE::WhiteKnight => self.white_knights.set(index),
-            PIECE::WhiteBishop => self.white_bishops.set(index),
-            PIECE::WhiteRook => self.white_rooks.set(index),
-            PIECE::WhiteQueen => self.white_queens.set(index),
-            PIECE::WhiteKing => self.white_king.set(index),
-
-            PIECE::BlackPawn => self.black_pawns.set(index),
-            PIECE::BlackKnight => self.black_knights.set(index),
-            PIECE::BlackBishop => self.black_bishops.set(index),
-            PIECE::BlackRook => self.black_rooks.set(index),
-            PIECE::BlackQueen => self.black_queens.set(index),
-            PIECE::BlackKing => self.black_king.set(index),
-
-            PIECE::Empty => {
+        let color = match piece.color() {
+            Some(color) => color,
+            None => {
                 println!(
                     "Tried to add empty piece at square {} on below board",
                     SQUARE::from(index)
@@ -76,14 +39,35 @@ impl<'a> Board<'a> {
                 println!("{}", self);
                 panic!("Cannot add empty piece");
             }
-        }
+        };
+
+        self.pieces[color.index()][piece.piece_type().index()].set(index);
+        self.color_occupancy[color.index()].set(index);
+        self.mailbox[index] = piece;
+        self.key ^= Board::piece_key(piece, index);
     }
 
     pub fn make_move(&mut self, move_: Move) {
-        let source_square = move_.source;
-        let target_square = move_.target;
-        let target_index = move_.target.index();
-        let source_index = move_.source.index();
+        self.make_move_unchecked(move_);
+
+        // debug builds check that legal moves only ever lead to legal positions
+        debug_assert!(
+            self.validate().is_empty(),
+            "{} led to an invalid position {}: {:?}",
+            move_,
+            self.to_fen(),
+            self.validate()
+        );
+        debug_assert_eq!(self.key, self.compute_key(), "{} broke the key", move_);
+    }
+
+    // plays a pseudo-legal move without validating the result, so it may leave the mover in check.
+    // Legality checks use this to try a move out on a copy of the board
+    pub(crate) fn make_move_unchecked(&mut self, move_: Move) {
+        let source_square = move_.source();
+        let target_square = move_.target();
+        let target_index = move_.target().index();
+        let source_index = move_.source().index();
         let source_piece = self.piece_at_index(source_index);
         let source_color = match source_piece.color() {
             Some(COLOR::WHITE) => COLOR::WHITE,
@@ -94,8 +78,14 @@ impl<'a> Board<'a> {
             }
         };
 
-        // updating halfmove clock for capture
-        if self.piece_at_index(target_index).not_empty() {
+        // castling rights, en passant and side to move are swapped out of the key as a whole
+        self.key ^= self.state_key();
+
+        // every move counts towards the fifty-move rule, unless it's a capture or a pawn move
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+
+        // updating halfmove clock for capture (castles "capture" their own rook, so ask the move)
+        if move_.is_capture() {
             self.halfmove_clock = 0;
         }
 
@@ -110,7 +100,7 @@ impl<'a> Board<'a> {
             self.halfmove_clock = 0;
             match source_color {
                 COLOR::WHITE => {
-                    if source_square.rank() == RANK::Rank2 && move_.target.rank() == RANK::Rank4 {
+                    if source_square.rank() == RANK::Rank2 && move_.target().rank() == RANK::Rank4 {
                         let en_passant_target = bits_to_index(
                             north(source_square.bits())
                                 .expect("Pawn double move cannot be on rank 8"),
@@ -119,7 +109,7 @@ impl<'a> Board<'a> {
                     }
                 }
                 COLOR::BLACK => {
-                    if source_square.rank() == RANK::Rank7 && move_.target.rank() == RANK::Rank5 {
+                    if source_square.rank() == RANK::Rank7 && move_.target().rank() == RANK::Rank5 {
                         let en_passant_target = bits_to_index(
                             south(source_square.bits())
                                 .expect("Pawn double move cannot be on rank 1"),
@@ -131,34 +121,18 @@ impl<'a> Board<'a> {
         }
 
         // perform castling move and return if castling:
-        match move_.castling {
-            Some(CASTLE::WhiteKingside) => {
-                self.remove_piece(SQUARE::E1.index());
-                self.remove_piece(SQUARE::H1.index());
-                self.add_piece(SQUARE::G1.index(), PIECE::WhiteKing);
-                self.add_piece(SQUARE::F1.index(), PIECE::WhiteRook);
-                self.castling_rights.remove_color(COLOR::WHITE);
-            }
-            Some(CASTLE::WhiteQueenside) => {
-                self.remove_piece(SQUARE::E1.index());
-                self.remove_piece(SQUARE::A1.index());
-                self.add_piece(SQUARE::C1.index(), PIECE::WhiteKing);
-                self.add_piece(SQUARE::D1.index(), PIECE::WhiteRook);
-                self.castling_rights.remove_color(COLOR::WHITE);
-            }
-            Some(CASTLE::BlackKingside) => {
-                self.remove_piece(SQUARE::E8.index());
-                self.remove_piece(SQUARE::H8.index());
-                self.add_piece(SQUARE::G8.index(), PIECE::BlackKing);
-                self.add_piece(SQUARE::F8.index(), PIECE::BlackRook);
-                self.castling_rights.remove_color(COLOR::BLACK);
-            }
-            Some(CASTLE::BlackQueenside) => {
-                self.remove_piece(SQUARE::E8.index());
-                self.remove_piece(SQUARE::A8.index());
-                self.add_piece(SQUARE::C8.index(), PIECE::BlackKing);
-                self.add_piece(SQUARE::D8.index(), PIECE::BlackRook);
-                self.castling_rights.remove_color(COLOR::BLACK);
+        match move_.castling() {
+            // the move target is the castling rook, and in chess960 either piece may land on the
+            // other's starting square, so both are lifted before being put down
+            Some(castle) => {
+                self.remove_piece(source_index);
+                self.remove_piece(target_index);
+                self.add_piece(castle.king_target().index(), source_piece);
+                self.add_piece(
+                    castle.rook_target().index(),
+                    PieceType::ROOK.of_color(source_color),
+                );
+                self.castling_rights.remove_color(source_color);
             }
 
             // if not castling make move as normal
@@ -177,16 +151,13 @@ impl<'a> Board<'a> {
                 }
 
                 // handle promotion
-                match move_.promotion {
-                    Some(new_piece) => {
-                        self.remove_piece(target_index);
-                        self.add_piece(target_index, new_piece.of_color(source_color));
-                    }
-                    None => {}
+                if let Some(new_piece) = move_.promotion() {
+                    self.remove_piece(target_index);
+                    self.add_piece(target_index, new_piece.of_color(source_color));
                 }
 
                 // handle en passant capture
-                if move_.en_passant {
+                if move_.is_en_passant() {
                     // depending on color of moving piece, remove the piece one rank above or below the target square
                     match source_color {
                         COLOR::WHITE => {
@@ -216,27 +187,35 @@ impl<'a> Board<'a> {
                 if source_piece.piece_type() == PieceType::KING {
                     self.castling_rights.remove_color(source_color);
                 }
-
-                // updating castling rights for non-castle rook moves
-                if source_piece.piece_type() == PieceType::ROOK {
-                    match source_square {
-                        SQUARE::A1 => self.castling_rights.set(CASTLE::WhiteQueenside, false),
-                        SQUARE::H1 => self.castling_rights.set(CASTLE::WhiteKingside, false),
-                        SQUARE::A8 => self.castling_rights.set(CASTLE::BlackQueenside, false),
-                        SQUARE::H8 => self.castling_rights.set(CASTLE::BlackKingside, false),
-                        _ => {}
-                    }
-                }
             }
         }
 
-        // update clocks
-        self.halfmove_clock += 1;
+        // update fullmove number
         if source_color == COLOR::BLACK {
             self.fullmove_number += 1;
         }
 
         // change to_move
         self.to_move = self.to_move.opposite();
+
+        self.key ^= self.state_key();
+    }
+
+    // passes the turn without moving, for null move pruning. Only valid when not in check, since
+    // it would otherwise leave the king in check with the opponent to move
+    pub fn make_null_move(&mut self) {
+        debug_assert!(!self.in_check(self.to_move), "null move while in check");
+
+        self.key ^= self.state_key();
+
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        self.en_passant_target = None;
+        if self.to_move == COLOR::BLACK {
+            self.fullmove_number += 1;
+        }
+        self.to_move = self.to_move.opposite();
+
+        self.key ^= self.state_key();
+        debug_assert_eq!(self.key, self.compute_key(), "null move broke the key");
     }
 }
@@ -0,0 +1,187 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::enums::*;
+
+impl<'a> Board<'a> {
+    // every reason this position could never come up in a game. These are necessary conditions
+    // only: an empty list doesn't prove the position is reachable
+    pub fn validate(&self) -> Vec<PositionError> {
+        let mut errors = Vec::new();
+
+        // 1. Piece counts
+        for color in [COLOR::WHITE, COLOR::BLACK] {
+            let kings = self.pieces_of(color, PieceType::KING).count();
+            if kings != 1 {
+                errors.push(PositionError::KingCount(color, kings));
+            }
+
+            let pieces = self.occupancy_of_color(color).count();
+            if pieces > 16 {
+                errors.push(PositionError::TooManyPieces(color, pieces));
+            }
+
+            let pawns = self.pieces_of(color, PieceType::PAWN).count();
+            if pawns > 8 {
+                errors.push(PositionError::TooManyPawns(color, pawns));
+            }
+
+            // every piece beyond the starting set has to be a promoted pawn
+            let extra = |piece_type: PieceType, start: u32| {
+                self.pieces_of(color, piece_type)
+                    .count()
+                    .saturating_sub(start)
+            };
+            let promoted = extra(PieceType::QUEEN, 1)
+                + extra(PieceType::ROOK, 2)
+                + extra(PieceType::BISHOP, 2)
+                + extra(PieceType::KNIGHT, 2);
+            if pawns <= 8 && pawns + promoted > 8 {
+                errors.push(PositionError::TooManyPromotions(color));
+            }
+        }
+
+        // 2. Pawns never stand on either back rank
+        let back_ranks = Bitboard::new(RANK::Rank1.bits() | RANK::Rank8.bits());
+        let mut pawns = (self.pieces_of(COLOR::WHITE, PieceType::PAWN)
+            | self.pieces_of(COLOR::BLACK, PieceType::PAWN))
+            & back_ranks;
+        while let Some(index) = pawns.pop_lsb() {
+            errors.push(PositionError::PawnOnBackRank(SQUARE::from(index)));
+        }
+
+        // 3. Checks, which only make sense with one king on each side
+        let kings_in_place = [COLOR::WHITE, COLOR::BLACK]
+            .iter()
+            .all(|color| self.pieces_of(*color, PieceType::KING).count() == 1);
+        if kings_in_place {
+            if self.in_check(self.to_move.opposite()) {
+                errors.push(PositionError::OpponentInCheck);
+            }
+
+            let checkers = self.checkers().count();
+            if checkers > 2 {
+                errors.push(PositionError::TooManyCheckers(checkers));
+            }
+        }
+
+        // 4. Castling rights need the king and rook on their starting squares
+        for castle in CASTLES {
+            let rook_square = match self.castling_rights.rook_square(castle) {
+                Some(rook_square) => rook_square,
+                None => continue,
+            };
+            let color = castle.color();
+
+            let king = self.pieces_of(color, PieceType::KING);
+            let king_in_place = king.count() == 1 && {
+                let king_square = SQUARE::from_bits(king.bits());
+                let corner = match castle.is_kingside() {
+                    true => FILE::FileH,
+                    false => FILE::FileA,
+                };
+
+                king_square.rank() == castle.back_rank()
+                    && (rook_square.file() > king_square.file()) == castle.is_kingside()
+                    && (self.chess960
+                        || (king_square.file() == FILE::FileE && rook_square.file() == corner))
+            };
+            let rook_in_place =
+                self.piece_at_square(rook_square) == PieceType::ROOK.of_color(color);
+
+            if !king_in_place || !rook_in_place {
+                errors.push(PositionError::InvalidCastlingRights(castle));
+            }
+        }
+
+        // 5. The en passant square was just crossed by an enemy pawn's double push
+        if let Some(target) = self.en_passant_target {
+            let pusher = self.to_move.opposite();
+            let expected_rank = match pusher {
+                COLOR::WHITE => RANK::Rank3,
+                COLOR::BLACK => RANK::Rank6,
+            };
+
+            let is_empty = |square: Option<SQUARE>| {
+                square.is_some_and(|square| self.piece_at_square(square) == PIECE::Empty)
+            };
+            let pushed_pawn = target
+                .forward(pusher)
+                .map(|square| self.piece_at_square(square));
+
+            let valid = target.rank() == expected_rank
+                && is_empty(Some(target))
+                && is_empty(target.forward(self.to_move))
+                && pushed_pawn == Some(PieceType::PAWN.of_color(pusher));
+
+            if !valid {
+                errors.push(PositionError::InvalidEnPassant(target));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, STARTING_FEN};
+    use crate::enums::*;
+    use crate::lookup_table::LookupTable;
+
+    fn errors(fen: &str) -> Vec<PositionError> {
+        let lookup_table = LookupTable::new();
+        Board::from_fen(fen, &lookup_table).validate()
+    }
+
+    #[test]
+    fn starting_position_is_valid() {
+        assert_eq!(errors(STARTING_FEN), vec![]);
+    }
+
+    #[test]
+    fn two_kings() {
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+            vec![PositionError::KingCount(COLOR::WHITE, 2)]
+        );
+    }
+
+    #[test]
+    fn pawn_on_back_rank() {
+        assert_eq!(
+            errors("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            vec![PositionError::PawnOnBackRank(SQUARE::A8)]
+        );
+    }
+
+    #[test]
+    fn side_not_to_move_in_check() {
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            vec![PositionError::OpponentInCheck]
+        );
+    }
+
+    #[test]
+    fn castling_rights_without_rook() {
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+            vec![PositionError::InvalidCastlingRights(CASTLE::WhiteKingside)]
+        );
+    }
+
+    #[test]
+    fn en_passant_without_pushed_pawn() {
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"),
+            vec![PositionError::InvalidEnPassant(SQUARE::E6)]
+        );
+    }
+
+    #[test]
+    fn more_than_sixteen_pieces() {
+        let errors = errors("4k3/8/8/NNNNNNNN/8/PPPPPPPP/8/4K3 w - - 0 1");
+        assert!(errors.contains(&PositionError::TooManyPieces(COLOR::WHITE, 17)));
+        assert!(errors.contains(&PositionError::TooManyPromotions(COLOR::WHITE)));
+    }
+}
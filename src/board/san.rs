@@ -0,0 +1,255 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+
+impl<'a> Board<'a> {
+    // Standard Algebraic Notation for a legal move in this position, e.g. Nbd7, exd6, e8=Q+, O-O#
+    pub fn to_san(&self, m: Move) -> String {
+        let mut san = match m.castling() {
+            Some(castle) if castle.is_kingside() => String::from("O-O"),
+            Some(_) => String::from("O-O-O"),
+            None => self.san_without_suffix(m),
+        };
+
+        // check and mate suffixes depend on the position after the move
+        let mut board = self.clone();
+        board.make_move(m);
+        if board.in_check(board.to_move) {
+            match board.generate_moves_for_color(board.to_move).is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+        }
+
+        san
+    }
+
+    // a line of legal moves from this position in SAN with move numbers, e.g. 1. e4 e5 2. Nf3 or
+    // 12... Kh8 13. Qh7#
+    pub fn line_to_san(&self, line: &[Move]) -> String {
+        let mut board = self.clone();
+        let mut parts = Vec::new();
+
+        for (i, m) in line.iter().enumerate() {
+            match (board.to_move, i) {
+                (COLOR::WHITE, _) => parts.push(format!("{}.", board.fullmove_number)),
+                (COLOR::BLACK, 0) => parts.push(format!("{}...", board.fullmove_number)),
+                (COLOR::BLACK, _) => {}
+            }
+            parts.push(board.to_san(*m));
+            board.make_move(*m);
+        }
+
+        parts.join(" ")
+    }
+
+    fn san_without_suffix(&self, m: Move) -> String {
+        let piece_type = self.piece_at_square(m.source()).piece_type();
+        let mut san = String::new();
+
+        // 1. Pawns name their file when capturing and never need any other disambiguation
+        if piece_type == PieceType::PAWN {
+            if m.is_capture() {
+                san.push(m.source().file().to_fen());
+                san.push('x');
+            }
+            san.push_str(&m.target().to_fen());
+
+            if let Some(promotion) = m.promotion() {
+                san.push('=');
+                san.push(promotion.to_char());
+            }
+            return san;
+        }
+
+        san.push(piece_type.to_char());
+
+        // 2. Other pieces of the same kind that can also legally reach the target
+        let rivals: Vec<SQUARE> = self
+            .generate_moves_for_color(self.to_move)
+            .iter()
+            .filter(|other| {
+                !other.is_castle()
+                    && other.target() == m.target()
+                    && other.source() != m.source()
+                    && self.piece_at_square(other.source()).piece_type() == piece_type
+            })
+            .map(|other| other.source())
+            .collect();
+
+        // 3. Prefer the file, then the rank, and only use both if neither alone is enough
+        if !rivals.is_empty() {
+            let file_is_unique = rivals.iter().all(|s| s.file() != m.source().file());
+            let rank_is_unique = rivals.iter().all(|s| s.rank() != m.source().rank());
+
+            if file_is_unique {
+                san.push(m.source().file().to_fen());
+            } else if rank_is_unique {
+                san.push(m.source().rank().to_fen());
+            } else {
+                san.push_str(&m.source().to_fen());
+            }
+        }
+
+        if m.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&m.target().to_fen());
+
+        san
+    }
+
+    // Finds the legal move written in SAN. Besides the strict form this accepts castles written
+    // with zeros, promotions without '=' or in lowercase, extra disambiguation, a missing or
+    // extra 'x', '-' between squares and trailing check marks and annotations like !? or +/-
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(|c| "+#!?=/-".contains(c));
+        let legal_moves = self.generate_moves_for_color(self.to_move);
+
+        // 1. Castles
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castle {
+            return legal_moves
+                .iter()
+                .find(|m| m.castling().map(|c| c.is_kingside()) == Some(kingside))
+                .copied();
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|c| !"x:-".contains(*c)).collect();
+
+        // 2. The moving piece, pawns have no letter (a lowercase b is always a file)
+        let piece_type = match chars.first() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let piece_type = PieceType::from_char(*c)?;
+                chars.remove(0);
+                piece_type
+            }
+            _ => PieceType::PAWN,
+        };
+
+        // 3. The promotion piece follows the target square's rank
+        let promotion = match chars.last() {
+            Some(c) if !c.is_ascii_digit() && piece_type == PieceType::PAWN => {
+                let promotion = PieceType::from_char(*c)?;
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        // 4. The target square is last, anything before it narrows down the source square
+        if chars.len() < 2 || chars.len() > 4 {
+            return None;
+        }
+        let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let target = SQUARE::from_string(&target)?;
+
+        let mut source_file = None;
+        let mut source_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' => source_file = Some(c),
+                '1'..='8' => source_rank = Some(c),
+                _ => return None,
+            }
+        }
+
+        // 5. Exactly one legal move has to fit everything that was written
+        let mut candidates = legal_moves.iter().filter(|m| {
+            !m.is_castle()
+                && m.target() == target
+                && m.promotion() == promotion
+                && self.piece_at_square(m.source()).piece_type() == piece_type
+                && source_file.is_none_or(|f| m.source().file().to_fen() == f)
+                && source_rank.is_none_or(|r| m.source().rank().to_fen() == r)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(m), None) => Some(*m),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, STARTING_FEN};
+    use crate::lookup_table::LookupTable;
+
+    // the SAN of a move given in UCI, checking it parses back to the same move
+    fn round_trip(board: &Board, uci: &str) -> String {
+        let m = board.parse_uci_move(uci).unwrap();
+        let san = board.to_san(m);
+        assert_eq!(board.parse_san(&san), Some(m), "{} read back", san);
+        san
+    }
+
+    #[test]
+    fn every_move_round_trips() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &lookup_table,
+        );
+
+        for m in board.generate_moves_for_color(board.to_move).iter() {
+            assert_eq!(board.parse_san(&board.to_san(*m)), Some(*m));
+        }
+    }
+
+    #[test]
+    fn disambiguation() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("4k3/8/8/R7/8/8/4K3/R6R w - - 0 1", &lookup_table);
+
+        assert_eq!(round_trip(&board, "a1d1"), "Rad1");
+        assert_eq!(round_trip(&board, "h1d1"), "Rhd1");
+        assert_eq!(round_trip(&board, "a1a3"), "R1a3");
+        assert_eq!(round_trip(&board, "a5a3"), "R5a3");
+        assert_eq!(board.parse_san("Rd1"), None);
+    }
+
+    #[test]
+    fn promotions() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", &lookup_table);
+
+        assert_eq!(round_trip(&board, "e7e8q"), "e8=Q");
+        assert_eq!(round_trip(&board, "e7e8n"), "e8=N");
+        assert_eq!(board.parse_san("e8Q"), board.parse_san("e8=Q"));
+        assert_eq!(board.parse_san("e8=q"), board.parse_san("e8=Q"));
+    }
+
+    #[test]
+    fn castles() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &lookup_table);
+
+        assert_eq!(round_trip(&board, "e1g1"), "O-O");
+        assert_eq!(round_trip(&board, "e1c1"), "O-O-O");
+        assert_eq!(board.parse_san("0-0"), board.parse_san("O-O"));
+        assert_eq!(board.parse_san("0-0-0"), board.parse_san("O-O-O"));
+    }
+
+    #[test]
+    fn annotations_and_mate() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(STARTING_FEN, &lookup_table);
+        assert_eq!(board.parse_san("Nf3!?"), board.parse_san("Nf3"));
+        assert!(board.parse_san("Nf3!?").is_some());
+
+        let board = Board::from_fen(
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+            &lookup_table,
+        );
+        assert_eq!(round_trip(&board, "d8h4"), "Qh4#");
+        assert_eq!(board.parse_san("Qh4"), board.parse_san("Qh4#"));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+
+impl<'a> Board<'a> {
+    // Static exchange evaluation: the material the side to move ends up with after the capture
+    // and every recapture on the target square, each side taking with its least valuable attacker
+    // and free to stop whenever going on would lose material. Sliders behind the pieces that
+    // already took join in as the squares in front of them empty
+    pub fn see(&self, m: Move) -> i32 {
+        if m.is_castle() {
+            return 0;
+        }
+
+        let source = m.source();
+        let target = m.target();
+        let mut occupancy = self.occupancy() ^ Bitboard::new(source.bits());
+
+        // 1. The first capture, which the side to move has already committed to
+        let mut gain = [0; 32];
+        gain[0] = match m.is_en_passant() {
+            true => {
+                let captured = target.forward(self.to_move.opposite()).unwrap();
+                occupancy ^= Bitboard::new(captured.bits());
+                PieceType::PAWN.value()
+            }
+            false => self.piece_at_square(target).piece_type().value(),
+        };
+
+        // whatever stands on the target square next, and so can be taken back
+        let mut on_target = self.piece_at_square(source).piece_type();
+        if let Some(promotion) = m.promotion() {
+            gain[0] += promotion.value() - PieceType::PAWN.value();
+            on_target = promotion;
+        }
+
+        // 2. Recaptures, alternating sides
+        let mut attackers = self.attackers_to(target, occupancy);
+        let mut side = self.to_move.opposite();
+        let mut depth = 0;
+        loop {
+            let own_attackers = attackers & occupancy & self.occupancy_of_color(side);
+            let attacker = match PIECE_TYPES
+                .iter()
+                .find(|piece_type| (own_attackers & self.pieces_of(side, **piece_type)).any())
+            {
+                Some(piece_type) => *piece_type,
+                None => break,
+            };
+            let mut from = own_attackers & self.pieces_of(side, attacker);
+            let from = SQUARE::from(from.pop_lsb().unwrap());
+
+            // a slider x-raying the target through the piece that takes joins in once it's gone
+            let mut xrays = self.xray_attackers(target, occupancy);
+            while let Some(index) = xrays.pop_lsb() {
+                let xray = SQUARE::from(index);
+                if self
+                    .lookup_table
+                    .get_between(target, xray)
+                    .is_set(from.index())
+                {
+                    attackers.set(index);
+                }
+            }
+            occupancy ^= Bitboard::new(from.bits());
+
+            // the king may only take last, when nothing can take it back
+            let defenders = attackers & occupancy & self.occupancy_of_color(side.opposite());
+            if attacker == PieceType::KING && defenders.any() {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = on_target.value() - gain[depth - 1];
+            on_target = attacker;
+
+            // neither side can come out ahead by going on
+            if (-gain[depth - 1]).max(gain[depth]) < 0 {
+                break;
+            }
+
+            side = side.opposite();
+        }
+
+        // 3. Each side only takes when it pays off, counted back from the last capture
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+
+        gain[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::enums::*;
+    use crate::lookup_table::LookupTable;
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(fen, &lookup_table);
+        board.see(board.parse_uci_move(uci).unwrap())
+    }
+
+    #[test]
+    fn xray_attackers_look_through_one_piece() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("4k3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", &lookup_table);
+
+        // the rook on d1 sees d5 through the one on d2, the one on d7 attacks it directly
+        let xrays = board.xray_attackers(SQUARE::D5, board.occupancy());
+        assert_eq!(xrays.bits(), SQUARE::D1.bits());
+    }
+
+    #[test]
+    fn undefended_and_defended_captures() {
+        assert_eq!(see("4k3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), 100);
+        assert_eq!(see("4k3/3r4/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+    }
+
+    #[test]
+    fn batteries_recapture_through_the_first_piece() {
+        // the second rook takes back once the first has gone
+        assert_eq!(see("4k3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+        // and a queen behind a bishop on a diagonal
+        assert_eq!(see("4k3/8/5b2/4p3/8/2B5/1Q6/4K3 w - - 0 1", "c3e5"), 100);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+
+impl<'a> Board<'a> {
+    // Reads a UCI long algebraic move (e2e4, e7e8q) and fills in its flags from the position.
+    // Castles may be written as the king's destination (e1g1) or, as chess960 requires, as the
+    // king taking its rook (e1h1). In chess960 the first form is always a plain king move
+    pub fn parse_uci_move(&self, uci: &str) -> Result<Move, MoveError> {
+        let invalid = || MoveError::InvalidFormat(uci.to_string());
+
+        // 1. Split into source, target and promotion
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return Err(invalid());
+        }
+        let source = SQUARE::from_string(&uci[0..2]).ok_or_else(invalid)?;
+        let target = SQUARE::from_string(&uci[2..4]).ok_or_else(invalid)?;
+        let promotion = match uci[4..].chars().next() {
+            Some(c) if "nbrq".contains(c) => PieceType::from_char(c),
+            Some(_) => return Err(invalid()),
+            None => None,
+        };
+
+        // 2. Match against the pseudo-legal moves, which know about captures, en passant and castles
+        let matches = |m: &Move| match m.castling() {
+            Some(castle) => {
+                m.source() == source
+                    && promotion.is_none()
+                    && (m.target() == target || (!self.chess960 && castle.king_target() == target))
+            }
+            None => m.source() == source && m.target() == target && m.promotion() == promotion,
+        };
+
+        let m = self
+            .generate_pseudo_moves_for_color(self.to_move)
+            .iter()
+            .copied()
+            .find(|m| matches(m))
+            .unwrap_or_else(|| self.guess_move(source, target, promotion));
+
+        // 3. Whatever the move turned out to be, it has to be legal
+        self.validate_move(m).map_err(MoveError::Illegal)?;
+        Ok(m)
+    }
+
+    // the move the player most likely meant when it isn't pseudo-legal, flagged from what is on
+    // the board, so that validate_move can tell them what is wrong with it
+    fn guess_move(&self, source: SQUARE, target: SQUARE, promotion: Option<PieceType>) -> Move {
+        let piece = self.piece_at_square(source);
+        let capture = self.piece_at_square(target).not_empty();
+        let back_rank = CASTLE::of_color(self.to_move, true).back_rank();
+
+        let flag = match promotion {
+            Some(promotion) => MoveFlag::promotion_to(promotion, capture),
+            None if piece.is_pawn() && Some(target) == self.en_passant_target => {
+                MoveFlag::EnPassant
+            }
+            None if piece.is_pawn() && (source.rank() - target.rank()).abs() == 2 => {
+                MoveFlag::DoublePawnPush
+            }
+            // a king stepping two files along its back rank wants to castle
+            None if piece.is_king()
+                && !self.chess960
+                && source.rank() == back_rank
+                && target.rank() == back_rank
+                && (source.file() - target.file()).abs() == 2 =>
+            {
+                let kingside = target.file() > source.file();
+                let castle = CASTLE::of_color(self.to_move, kingside);
+                let target = self.castling_rights.rook_square(castle).unwrap_or(target);
+                let flag = match kingside {
+                    true => MoveFlag::KingCastle,
+                    false => MoveFlag::QueenCastle,
+                };
+                return Move::new(source, target, flag);
+            }
+            None if capture => MoveFlag::Capture,
+            None => MoveFlag::Quiet,
+        };
+
+        Move::new(source, target, flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, STARTING_FEN};
+    use crate::enums::*;
+    use crate::lookup_table::LookupTable;
+
+    #[test]
+    fn normal_moves_and_promotions() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(STARTING_FEN, &lookup_table);
+        let m = board.parse_uci_move("e2e4").unwrap();
+        assert_eq!((m.source(), m.target()), (SQUARE::E2, SQUARE::E4));
+        assert_eq!(m.flag(), MoveFlag::DoublePawnPush);
+
+        let board = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", &lookup_table);
+        let m = board.parse_uci_move("a7a8n").unwrap();
+        assert_eq!(m.promotion(), Some(PieceType::KNIGHT));
+        assert!(!m.is_capture());
+
+        let m = board.parse_uci_move("a7b8q").unwrap();
+        assert_eq!(m.promotion(), Some(PieceType::QUEEN));
+        assert!(m.is_capture());
+    }
+
+    #[test]
+    fn standard_castles_by_the_kings_square() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &lookup_table);
+
+        // stored as king takes rook, whichever way it was written
+        let m = board.parse_uci_move("e1g1").unwrap();
+        assert_eq!(m.castling(), Some(CASTLE::WhiteKingside));
+        assert_eq!(m.target(), SQUARE::H1);
+        assert_eq!(board.parse_uci_move("e1h1"), Ok(m));
+        assert_eq!(m.to_uci(false), "e1g1");
+    }
+
+    #[test]
+    fn chess960_castles_by_taking_the_rook() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1", &lookup_table);
+
+        let m = board.parse_uci_move("e1b1").unwrap();
+        assert_eq!(m.castling(), Some(CASTLE::WhiteQueenside));
+        assert_eq!(m.to_uci(true), "e1b1");
+
+        // the king's destination is a plain king move here
+        let m = board.parse_uci_move("e1c1");
+        assert!(
+            m.is_err_and(|error| error == MoveError::Illegal(IllegalMoveReason::InvalidMovement))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_illegal_moves() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(STARTING_FEN, &lookup_table);
+
+        for uci in ["", "e2", "e2e4e", "e2e4k", "i2i4", "e2e4qq", "é2e4"] {
+            assert_eq!(
+                board.parse_uci_move(uci),
+                Err(MoveError::InvalidFormat(uci.to_string())),
+                "{}",
+                uci
+            );
+        }
+
+        assert_eq!(
+            board.parse_uci_move("e2e5"),
+            Err(MoveError::Illegal(IllegalMoveReason::InvalidMovement))
+        );
+        assert_eq!(
+            board.parse_uci_move("e7e5"),
+            Err(MoveError::Illegal(IllegalMoveReason::NotYourTurn(
+                SQUARE::E7
+            )))
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::board::Board;
+use crate::enums::*;
+
+// Random numbers for every piece on every square, every castling right (per rook file, for
+// chess960), the en passant file and the side to move. A position's key is the XOR of the numbers
+// for everything in it, so that equal positions get equal keys and a move only has to XOR in
+// what it changes. The numbers are generated at compile time so keys are the same in every run
+struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castling: [[u64; 8]; 4],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+}
+
+static ZOBRIST: ZobristKeys = ZobristKeys::generate();
+
+// splitmix64, returns the next state and a random number
+const fn next_random(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (state, z ^ (z >> 31))
+}
+
+impl ZobristKeys {
+    const fn generate() -> ZobristKeys {
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castling: [[0; 8]; 4],
+            en_passant: [0; 8],
+            black_to_move: 0,
+        };
+        let mut state = 0x2545F4914F6CDD1D;
+        let mut random;
+
+        let mut color = 0;
+        while color < 2 {
+            let mut piece_type = 0;
+            while piece_type < 6 {
+                let mut square = 0;
+                while square < 64 {
+                    (state, random) = next_random(state);
+                    keys.pieces[color][piece_type][square] = random;
+                    square += 1;
+                }
+                piece_type += 1;
+            }
+            color += 1;
+        }
+
+        let mut castle = 0;
+        while castle < 4 {
+            let mut file = 0;
+            while file < 8 {
+                (state, random) = next_random(state);
+                keys.castling[castle][file] = random;
+                file += 1;
+            }
+            castle += 1;
+        }
+
+        let mut file = 0;
+        while file < 8 {
+            (state, random) = next_random(state);
+            keys.en_passant[file] = random;
+            file += 1;
+        }
+
+        (_, random) = next_random(state);
+        keys.black_to_move = random;
+
+        keys
+    }
+}
+
+impl<'a> Board<'a> {
+    pub(crate) fn piece_key(piece: PIECE, index: usize) -> u64 {
+        let color = piece.color().expect("Empty piece has no key");
+        ZOBRIST.pieces[color.index()][piece.piece_type().index()][index]
+    }
+
+    // everything in the key apart from the pieces. The en passant square only counts when a pawn
+    // could actually take on it, since otherwise the position is the same one as without it
+    pub(crate) fn state_key(&self) -> u64 {
+        let mut key = 0;
+
+        for (i, castle) in CASTLES.iter().enumerate() {
+            if let Some(file) = self.castling_rights.rook_file(*castle) {
+                key ^= ZOBRIST.castling[i][file.index()];
+            }
+        }
+
+        if let Some(target) = self.en_passant_target {
+            let capturers = self
+                .lookup_table
+                .get_pawn_attacks(target, self.to_move.opposite())
+                & self.pieces_of(self.to_move, PieceType::PAWN);
+            if capturers.any() {
+                key ^= ZOBRIST.en_passant[target.file().index()];
+            }
+        }
+
+        if self.to_move == COLOR::BLACK {
+            key ^= ZOBRIST.black_to_move;
+        }
+
+        key
+    }
+
+    // the key built from scratch, make_move keeps self.key equal to this incrementally
+    pub fn compute_key(&self) -> u64 {
+        let mut key = self.state_key();
+        for (index, piece) in self.mailbox.iter().enumerate() {
+            if piece.not_empty() {
+                key ^= Board::piece_key(*piece, index);
+            }
+        }
+        key
+    }
+}
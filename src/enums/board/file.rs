@@ -46,6 +46,22 @@ impl FILE {
     }
 }
 
+impl From<usize> for FILE {
+    fn from(item: usize) -> Self {
+        match item {
+            0 => FILE::FileA,
+            1 => FILE::FileB,
+            2 => FILE::FileC,
+            3 => FILE::FileD,
+            4 => FILE::FileE,
+            5 => FILE::FileF,
+            6 => FILE::FileG,
+            7 => FILE::FileH,
+            _ => panic!("Invalid file"),
+        }
+    }
+}
+
 impl Add for FILE {
     type Output = i8;
 
@@ -172,6 +172,10 @@ impl SQUARE {
         }
     }
 
+    pub fn from_file_rank(file: FILE, rank: RANK) -> SQUARE {
+        SQUARE::from(rank.index() * 8 + file.index())
+    }
+
     // can only be used on bitboards with a single bit set
     pub fn from_bits(bits: u64) -> SQUARE {
         assert!(bits == 0 || bits.count_ones() == 1);
@@ -207,7 +211,7 @@ impl SQUARE {
             _ => return None,
         };
 
-        Some(SQUARE::from(file.index() * 8 + rank.index()))
+        Some(SQUARE::from_file_rank(file, rank))
     }
 
     pub fn to_fen(&self) -> String {
@@ -228,6 +232,14 @@ impl SQUARE {
         }
     }
 
+    // one square towards the opponent's side for a pawn of the given color
+    pub fn forward(&self, color: COLOR) -> Option<SQUARE> {
+        match color {
+            COLOR::WHITE => self.north(),
+            COLOR::BLACK => self.south(),
+        }
+    }
+
     pub fn east(&self) -> Option<SQUARE> {
         match self.file() {
             FILE::FileH => None,
@@ -0,0 +1,12 @@
+use strum_macros::Display;
+
+// how a stored search score relates to the true score of the position
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    // every move was searched inside the window, the score is exact
+    Exact,
+    // a move failed high, the true score is at least this
+    Lower,
+    // no move reached alpha, the true score is at most this
+    Upper,
+}
@@ -1,20 +1,64 @@
-use crate::enums::SQUARE;
+use crate::enums::*;
 use strum_macros::Display;
 
-pub const WKC_SQUARES: [SQUARE; 2] = [SQUARE::F1, SQUARE::G1];
-pub const WQC_SQUARES: [SQUARE; 3] = [SQUARE::D1, SQUARE::C1, SQUARE::B1];
-pub const BKC_SQUARES: [SQUARE; 2] = [SQUARE::F8, SQUARE::G8];
-pub const BQC_SQUARES: [SQUARE; 3] = [SQUARE::D8, SQUARE::C8, SQUARE::B8];
-
-pub const WKC_BITS: u64 = 0x0000000000000060;
-pub const WQC_BITS: u64 = 0x000000000000000E;
-pub const BKC_BITS: u64 = 0x6000000000000000;
-pub const BQC_BITS: u64 = 0x0E00000000000000;
-
-#[derive(Debug, Clone, Copy, Display)]
+#[derive(Debug, Clone, Copy, Display, PartialEq, Eq)]
 pub enum CASTLE {
     WhiteKingside = 0,
     WhiteQueenside = 1,
     BlackKingside = 2,
     BlackQueenside = 3,
 }
+
+pub const CASTLES: [CASTLE; 4] = [
+    CASTLE::WhiteKingside,
+    CASTLE::WhiteQueenside,
+    CASTLE::BlackKingside,
+    CASTLE::BlackQueenside,
+];
+
+impl CASTLE {
+    pub fn of_color(color: COLOR, kingside: bool) -> CASTLE {
+        match (color, kingside) {
+            (COLOR::WHITE, true) => CASTLE::WhiteKingside,
+            (COLOR::WHITE, false) => CASTLE::WhiteQueenside,
+            (COLOR::BLACK, true) => CASTLE::BlackKingside,
+            (COLOR::BLACK, false) => CASTLE::BlackQueenside,
+        }
+    }
+
+    pub fn color(&self) -> COLOR {
+        match self {
+            CASTLE::WhiteKingside | CASTLE::WhiteQueenside => COLOR::WHITE,
+            CASTLE::BlackKingside | CASTLE::BlackQueenside => COLOR::BLACK,
+        }
+    }
+
+    pub fn is_kingside(&self) -> bool {
+        match self {
+            CASTLE::WhiteKingside | CASTLE::BlackKingside => true,
+            CASTLE::WhiteQueenside | CASTLE::BlackQueenside => false,
+        }
+    }
+
+    pub fn back_rank(&self) -> RANK {
+        match self.color() {
+            COLOR::WHITE => RANK::Rank1,
+            COLOR::BLACK => RANK::Rank8,
+        }
+    }
+
+    // wherever the king and rook start (chess960 included) they always end up on the same squares
+    pub fn king_target(&self) -> SQUARE {
+        match self.is_kingside() {
+            true => SQUARE::from_file_rank(FILE::FileG, self.back_rank()),
+            false => SQUARE::from_file_rank(FILE::FileC, self.back_rank()),
+        }
+    }
+
+    pub fn rook_target(&self) -> SQUARE {
+        match self.is_kingside() {
+            true => SQUARE::from_file_rank(FILE::FileF, self.back_rank()),
+            false => SQUARE::from_file_rank(FILE::FileD, self.back_rank()),
+        }
+    }
+}
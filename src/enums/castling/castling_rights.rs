@@ -1,66 +1,152 @@
+use crate::bitboard::Bitboard;
+use crate::board::Board;
 use crate::enums::*;
 
-#[derive(Debug, Clone, Copy)]
+// Each right remembers the file of the rook it castles with, so that chess960
+// positions (where the rooks can start on any file) castle with the right rook.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CastlingRights {
-    pub white_kingside: bool,
-    pub white_queenside: bool,
-    pub black_kingside: bool,
-    pub black_queenside: bool,
+    pub white_kingside: Option<FILE>,
+    pub white_queenside: Option<FILE>,
+    pub black_kingside: Option<FILE>,
+    pub black_queenside: Option<FILE>,
 }
 
 impl CastlingRights {
-    pub fn from_fen(fen: &str) -> Self {
-        let mut castling_rights = CastlingRights {
-            white_kingside: false,
-            white_queenside: false,
-            black_kingside: false,
-            black_queenside: false,
-        };
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: None,
+            white_queenside: None,
+            black_kingside: None,
+            black_queenside: None,
+        }
+    }
+
+    // Parses standard (KQkq), X-FEN (KQkq meaning the outermost rook on that side) and
+    // Shredder-FEN (rook files, e.g. HAha) castling fields against the pieces already on the board.
+    // Rights without a matching rook fall back to the a/h corner and are kept as written, so that
+    // Board::validate can report them instead of them silently disappearing.
+    pub fn from_fen(fen: &str, board: &Board) -> Self {
+        let mut castling_rights = CastlingRights::none();
+
         for c in fen.chars() {
-            match c {
-                'K' => castling_rights.white_kingside = true,
-                'Q' => castling_rights.white_queenside = true,
-                'k' => castling_rights.black_kingside = true,
-                'q' => castling_rights.black_queenside = true,
-                _ => (),
-            }
+            let color = match c.is_ascii_uppercase() {
+                true => COLOR::WHITE,
+                false => COLOR::BLACK,
+            };
+
+            let king = board.pieces_of(color, PieceType::KING);
+            let king_file = match king.count() {
+                1 => SQUARE::from_bits(king.bits()).file(),
+                _ => FILE::FileE,
+            };
+
+            let rook_files = CastlingRights::rook_files_on(board, color);
+            let kingside_rooks = rook_files.iter().filter(|f| **f > king_file);
+            let queenside_rooks = rook_files.iter().filter(|f| **f < king_file);
+
+            let (castle, rook_file) = match c.to_ascii_lowercase() {
+                'k' => (
+                    CASTLE::of_color(color, true),
+                    kingside_rooks.last().copied().unwrap_or(FILE::FileH),
+                ),
+                'q' => (
+                    CASTLE::of_color(color, false),
+                    queenside_rooks.copied().next().unwrap_or(FILE::FileA),
+                ),
+                file @ 'a'..='h' => {
+                    let rook_file = FILE::from(file as usize - 'a' as usize);
+                    (CASTLE::of_color(color, rook_file > king_file), rook_file)
+                }
+                _ => continue,
+            };
+
+            castling_rights.set(castle, Some(rook_file));
         }
+
         castling_rights
     }
 
-    pub fn to_fen(&self) -> String {
+    // X-FEN: K/Q/k/q when the rook is the outermost one on its side, otherwise the rook's file
+    pub fn to_fen(&self, board: &Board) -> String {
         let mut fen = String::new();
-        if self.white_kingside {
-            fen.push('K');
-        }
-        if self.white_queenside {
-            fen.push('Q');
-        }
-        if self.black_kingside {
-            fen.push('k');
-        }
-        if self.black_queenside {
-            fen.push('q');
+
+        for castle in CASTLES {
+            let rook_file = match self.rook_file(castle) {
+                Some(rook_file) => rook_file,
+                None => continue,
+            };
+
+            let rook_files = CastlingRights::rook_files_on(board, castle.color());
+            let outermost = match castle.is_kingside() {
+                true => rook_files.last(),
+                false => rook_files.first(),
+            };
+
+            // a corner right whose rook is missing was read from K/Q/k/q, so it is written back that way
+            let corner = match castle.is_kingside() {
+                true => FILE::FileH,
+                false => FILE::FileA,
+            };
+            let written_as_side = outermost == Some(&rook_file)
+                || (rook_file == corner && !rook_files.contains(&rook_file));
+
+            let c = match (written_as_side, castle.is_kingside()) {
+                (true, true) => 'k',
+                (true, false) => 'q',
+                (false, _) => rook_file.to_fen(),
+            };
+
+            match castle.color() {
+                COLOR::WHITE => fen.push(c.to_ascii_uppercase()),
+                COLOR::BLACK => fen.push(c),
+            }
         }
+
         if fen.is_empty() {
             fen.push('-');
         }
         fen
     }
 
+    // files of the rooks of the given color on its back rank, from file a to file h
+    fn rook_files_on(board: &Board, color: COLOR) -> Vec<FILE> {
+        let back_rank = CASTLE::of_color(color, true).back_rank();
+        let mut rooks = board.pieces_of(color, PieceType::ROOK) & Bitboard::new(back_rank.bits());
+
+        let mut files = Vec::new();
+        while let Some(index) = rooks.pop_lsb() {
+            files.push(SQUARE::from(index).file());
+        }
+        files
+    }
+
+    // whether every right castles with a rook from the a/h corner, as in standard chess
+    pub fn is_standard(&self) -> bool {
+        CASTLES.iter().all(|castle| match self.rook_file(*castle) {
+            Some(file) if castle.is_kingside() => file == FILE::FileH,
+            Some(file) => file == FILE::FileA,
+            None => true,
+        })
+    }
+
     pub fn any(&self) -> bool {
-        self.white_kingside || self.white_queenside || self.black_kingside || self.black_queenside
+        self.any_white() || self.any_black()
     }
 
     pub fn any_white(&self) -> bool {
-        self.white_kingside || self.white_queenside
+        self.white_kingside.is_some() || self.white_queenside.is_some()
     }
 
     pub fn any_black(&self) -> bool {
-        self.black_kingside || self.black_queenside
+        self.black_kingside.is_some() || self.black_queenside.is_some()
     }
 
     pub fn get(&self, castle: CASTLE) -> bool {
+        self.rook_file(castle).is_some()
+    }
+
+    pub fn rook_file(&self, castle: CASTLE) -> Option<FILE> {
         match castle {
             CASTLE::WhiteKingside => self.white_kingside,
             CASTLE::WhiteQueenside => self.white_queenside,
@@ -69,61 +155,52 @@ impl CastlingRights {
         }
     }
 
-    pub fn set_index(&mut self, index: usize, value: bool) {
-        let castle = match index {
-            0 => CASTLE::WhiteKingside,
-            1 => CASTLE::WhiteQueenside,
-            2 => CASTLE::BlackKingside,
-            3 => CASTLE::BlackQueenside,
-            _ => panic!("Invalid index"),
-        };
-
-        self.set(castle, value);
+    pub fn rook_square(&self, castle: CASTLE) -> Option<SQUARE> {
+        self.rook_file(castle)
+            .map(|file| SQUARE::from_file_rank(file, castle.back_rank()))
     }
 
-    pub fn set(&mut self, castle: CASTLE, value: bool) {
+    pub fn set(&mut self, castle: CASTLE, rook_file: Option<FILE>) {
         match castle {
-            CASTLE::WhiteKingside => self.white_kingside = value,
-            CASTLE::WhiteQueenside => self.white_queenside = value,
-            CASTLE::BlackKingside => self.black_kingside = value,
-            CASTLE::BlackQueenside => self.black_queenside = value,
+            CASTLE::WhiteKingside => self.white_kingside = rook_file,
+            CASTLE::WhiteQueenside => self.white_queenside = rook_file,
+            CASTLE::BlackKingside => self.black_kingside = rook_file,
+            CASTLE::BlackQueenside => self.black_queenside = rook_file,
         }
     }
 
+    pub fn remove(&mut self, castle: CASTLE) {
+        self.set(castle, None);
+    }
+
     pub fn remove_color(&mut self, color: COLOR) {
-        match color {
-            COLOR::WHITE => {
-                self.set(CASTLE::WhiteQueenside, false);
-                self.set(CASTLE::WhiteKingside, false);
-            }
-            COLOR::BLACK => {
-                self.set(CASTLE::BlackKingside, false);
-                self.set(CASTLE::BlackQueenside, false);
+        self.remove(CASTLE::of_color(color, true));
+        self.remove(CASTLE::of_color(color, false));
+    }
+
+    // a castling rook leaving its square (moved or captured) loses that side's right
+    pub fn remove_rook(&mut self, color: COLOR, square: SQUARE) {
+        for kingside in [true, false] {
+            let castle = CASTLE::of_color(color, kingside);
+            if self.rook_square(castle) == Some(square) {
+                self.remove(castle);
             }
         }
     }
 
     pub fn give_color(&mut self, color: COLOR) {
-        match color {
-            COLOR::WHITE => {
-                self.set(CASTLE::WhiteKingside, true);
-                self.set(CASTLE::WhiteQueenside, true);
-            }
-            COLOR::BLACK => {
-                self.set(CASTLE::BlackKingside, true);
-                self.set(CASTLE::BlackQueenside, true);
-            }
-        }
+        self.set(CASTLE::of_color(color, true), Some(FILE::FileH));
+        self.set(CASTLE::of_color(color, false), Some(FILE::FileA));
     }
 }
 
 impl Default for CastlingRights {
     fn default() -> Self {
         CastlingRights {
-            white_kingside: true,
-            white_queenside: true,
-            black_kingside: true,
-            black_queenside: true,
+            white_kingside: Some(FILE::FileH),
+            white_queenside: Some(FILE::FileA),
+            black_kingside: Some(FILE::FileH),
+            black_queenside: Some(FILE::FileA),
         }
     }
 }
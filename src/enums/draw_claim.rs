@@ -0,0 +1,19 @@
+use crate::enums::*;
+use strum_macros::Display;
+
+// draws a player may claim, which unlike the automatic ones in Board::endgame don't end the game
+// on their own
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum DrawClaim {
+    FiftyMoveRule,
+    ThreefoldRepetition,
+}
+
+impl DrawClaim {
+    pub fn endgame(&self) -> ENDGAME {
+        match self {
+            DrawClaim::FiftyMoveRule => ENDGAME::FiftyMoveRule,
+            DrawClaim::ThreefoldRepetition => ENDGAME::ThreefoldRepetition,
+        }
+    }
+}
@@ -6,6 +6,12 @@ pub enum ENDGAME {
     Checkmate = 0,
     Stalemate = 1,
     InsufficientMaterial = 2,
+    // only when claimed, see DrawClaim
     FiftyMoveRule = 3,
     ThreefoldRepetition = 4,
+    // 150 plies without a capture or pawn move end the game without a claim
+    SeventyFiveMoveRule = 5,
+    FivefoldRepetition = 6,
+    // neither side can ever mate although there is material left, like fully locked pawns
+    DeadPosition = 7,
 }
@@ -0,0 +1,41 @@
+use crate::enums::*;
+use strum_macros::Display;
+
+// outcome of a game as written in PGN
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Ongoing,
+}
+
+impl GameResult {
+    // result of a game that ended in this way with the given side to move
+    pub fn from_endgame(endgame: ENDGAME, to_move: COLOR) -> GameResult {
+        match (endgame, to_move) {
+            (ENDGAME::Checkmate, COLOR::WHITE) => GameResult::BlackWins,
+            (ENDGAME::Checkmate, COLOR::BLACK) => GameResult::WhiteWins,
+            _ => GameResult::Draw,
+        }
+    }
+
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        }
+    }
+
+    pub fn from_pgn(s: &str) -> Option<GameResult> {
+        match s {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Ongoing),
+            _ => None,
+        }
+    }
+}
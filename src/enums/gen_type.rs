@@ -0,0 +1,15 @@
+use strum_macros::Display;
+
+// which subset of pseudo-legal moves the generator should produce
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum GenType {
+    All,
+    // captures (including en passant) and every promotion
+    Captures,
+    // everything that isn't in Captures, including castles
+    Quiets,
+    // moves that might get the king out of check: king steps, captures of the checker and blocks
+    Evasions,
+    // the subset of Quiets that gives check
+    QuietChecks,
+}
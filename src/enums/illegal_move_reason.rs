@@ -0,0 +1,76 @@
+use crate::enums::*;
+use std::fmt;
+
+// why a move can't be played in a position, see Board::validate_move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IllegalMoveReason {
+    NullMove,
+    EmptySquare(SQUARE),
+    // the piece on the source square belongs to the side not to move
+    NotYourTurn(SQUARE),
+    FriendlyCapture(SQUARE),
+    KingCapture(SQUARE),
+    // the piece doesn't move like that, even on an empty board
+    InvalidMovement,
+    // the first piece standing between the source and the target
+    PathBlocked(SQUARE),
+    // the move's capture, promotion or double push flag doesn't agree with the board
+    FlagMismatch,
+    NoEnPassant,
+    NoCastlingRights(CASTLE),
+    // the first piece standing on the king's or rook's way
+    CastlingBlocked(SQUARE),
+    CastlingOutOfCheck,
+    // the first attacked square the king would cross or land on
+    CastlingThroughCheck(SQUARE),
+    // the piece on the source square is pinned to its king by the piece on this square
+    PiecePinned(SQUARE),
+    KingMovesIntoCheck,
+    // anything else that leaves the king attacked, like not answering a check
+    KingLeftInCheck,
+}
+
+impl fmt::Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalMoveReason::NullMove => write!(f, "null move"),
+            IllegalMoveReason::EmptySquare(square) => {
+                write!(f, "no piece on {}", square.to_fen())
+            }
+            IllegalMoveReason::NotYourTurn(square) => {
+                write!(
+                    f,
+                    "piece on {} belongs to the side not to move",
+                    square.to_fen()
+                )
+            }
+            IllegalMoveReason::FriendlyCapture(square) => {
+                write!(f, "can't capture own piece on {}", square.to_fen())
+            }
+            IllegalMoveReason::KingCapture(square) => {
+                write!(f, "can't capture the king on {}", square.to_fen())
+            }
+            IllegalMoveReason::InvalidMovement => write!(f, "piece can't move there"),
+            IllegalMoveReason::PathBlocked(square) => {
+                write!(f, "path blocked by the piece on {}", square.to_fen())
+            }
+            IllegalMoveReason::FlagMismatch => write!(f, "move doesn't match the position"),
+            IllegalMoveReason::NoEnPassant => write!(f, "en passant isn't possible here"),
+            IllegalMoveReason::NoCastlingRights(castle) => {
+                write!(f, "no castling rights for {}", castle)
+            }
+            IllegalMoveReason::CastlingBlocked(square) => {
+                write!(f, "castling blocked by the piece on {}", square.to_fen())
+            }
+            IllegalMoveReason::CastlingOutOfCheck => write!(f, "can't castle out of check"),
+            IllegalMoveReason::CastlingThroughCheck(square) => {
+                write!(f, "can't castle through check on {}", square.to_fen())
+            }
+            IllegalMoveReason::PiecePinned(square) => {
+                write!(f, "piece is pinned by the piece on {}", square.to_fen())
+            }
+            IllegalMoveReason::KingMovesIntoCheck => write!(f, "king can't move into check"),
+            IllegalMoveReason::KingLeftInCheck => write!(f, "move leaves the king in check"),
+        }
+    }
+}
@@ -1,11 +1,27 @@
 mod board;
+mod bound;
 mod castling;
 mod color;
+mod draw_claim;
 mod endgame;
+mod game_result;
+mod gen_type;
+mod illegal_move_reason;
+mod move_error;
+mod move_flag;
 mod piece;
+mod position_error;
 
 pub use board::*;
+pub use bound::*;
 pub use castling::*;
 pub use color::*;
+pub use draw_claim::*;
 pub use endgame::*;
+pub use game_result::*;
+pub use gen_type::*;
+pub use illegal_move_reason::*;
+pub use move_error::*;
+pub use move_flag::*;
 pub use piece::*;
+pub use position_error::*;
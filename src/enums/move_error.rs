@@ -0,0 +1,20 @@
+use crate::enums::*;
+use std::fmt;
+
+// why a move string couldn't be turned into a legal move, see Board::parse_uci_move
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    // not two squares followed by an optional promotion letter
+    InvalidFormat(String),
+    // a well formed move the position doesn't allow
+    Illegal(IllegalMoveReason),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::InvalidFormat(s) => write!(f, "invalid move format: {}", s),
+            MoveError::Illegal(reason) => write!(f, "illegal move: {}", reason),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::enums::*;
+use strum_macros::Display;
+
+// Stored in the top 4 bits of a packed move. Bit 2 marks a capture and bit 3 a promotion,
+// with the low two bits of a promotion selecting the piece (knight, bishop, rook, queen).
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum MoveFlag {
+    Quiet = 0,
+    DoublePawnPush = 1,
+    KingCastle = 2,
+    QueenCastle = 3,
+    Capture = 4,
+    EnPassant = 5,
+    KnightPromotion = 8,
+    BishopPromotion = 9,
+    RookPromotion = 10,
+    QueenPromotion = 11,
+    KnightPromotionCapture = 12,
+    BishopPromotionCapture = 13,
+    RookPromotionCapture = 14,
+    QueenPromotionCapture = 15,
+}
+
+impl MoveFlag {
+    pub fn from_bits(bits: u16) -> Option<MoveFlag> {
+        match bits {
+            0 => Some(MoveFlag::Quiet),
+            1 => Some(MoveFlag::DoublePawnPush),
+            2 => Some(MoveFlag::KingCastle),
+            3 => Some(MoveFlag::QueenCastle),
+            4 => Some(MoveFlag::Capture),
+            5 => Some(MoveFlag::EnPassant),
+            8 => Some(MoveFlag::KnightPromotion),
+            9 => Some(MoveFlag::BishopPromotion),
+            10 => Some(MoveFlag::RookPromotion),
+            11 => Some(MoveFlag::QueenPromotion),
+            12 => Some(MoveFlag::KnightPromotionCapture),
+            13 => Some(MoveFlag::BishopPromotionCapture),
+            14 => Some(MoveFlag::RookPromotionCapture),
+            15 => Some(MoveFlag::QueenPromotionCapture),
+            _ => None,
+        }
+    }
+
+    pub fn promotion_to(piece_type: PieceType, capture: bool) -> MoveFlag {
+        match (piece_type, capture) {
+            (PieceType::KNIGHT, false) => MoveFlag::KnightPromotion,
+            (PieceType::BISHOP, false) => MoveFlag::BishopPromotion,
+            (PieceType::ROOK, false) => MoveFlag::RookPromotion,
+            (PieceType::QUEEN, false) => MoveFlag::QueenPromotion,
+            (PieceType::KNIGHT, true) => MoveFlag::KnightPromotionCapture,
+            (PieceType::BISHOP, true) => MoveFlag::BishopPromotionCapture,
+            (PieceType::ROOK, true) => MoveFlag::RookPromotionCapture,
+            (PieceType::QUEEN, true) => MoveFlag::QueenPromotionCapture,
+            _ => panic!("Invalid promotion"),
+        }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        *self as u16 & 0b0100 != 0
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        *self as u16 & 0b1000 != 0
+    }
+
+    pub fn is_castle(&self) -> bool {
+        *self == MoveFlag::KingCastle || *self == MoveFlag::QueenCastle
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        match self.is_promotion() {
+            true => Some(PROMOTION_OPTIONS[(*self as u16 & 0b0011) as usize]),
+            false => None,
+        }
+    }
+}
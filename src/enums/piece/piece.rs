@@ -1,5 +1,3 @@
-use crate::bitboard::Bitboard;
-use crate::board::Board;
 use crate::enums::piece::PieceType;
 use crate::enums::COLOR;
 use strum_macros::Display;
@@ -25,6 +23,26 @@ pub enum PIECE {
 }
 
 impl PIECE {
+    pub fn from_char(c: char) -> Option<PIECE> {
+        match c {
+            'P' => Some(PIECE::WhitePawn),
+            'N' => Some(PIECE::WhiteKnight),
+            'B' => Some(PIECE::WhiteBishop),
+            'R' => Some(PIECE::WhiteRook),
+            'Q' => Some(PIECE::WhiteQueen),
+            'K' => Some(PIECE::WhiteKing),
+
+            'p' => Some(PIECE::BlackPawn),
+            'n' => Some(PIECE::BlackKnight),
+            'b' => Some(PIECE::BlackBishop),
+            'r' => Some(PIECE::BlackRook),
+            'q' => Some(PIECE::BlackQueen),
+            'k' => Some(PIECE::BlackKing),
+
+            _ => None,
+        }
+    }
+
     pub fn color(&self) -> Option<COLOR> {
         match self {
             PIECE::WhitePawn
@@ -43,27 +61,6 @@ impl PIECE {
         }
     }
 
-    // given a board, returns the bitboard of all pieces of this type
-    pub fn piece_bb(&self, board: &Board) -> Bitboard {
-        match self {
-            PIECE::WhitePawn => board.white_pawns,
-            PIECE::WhiteKnight => board.white_knights,
-            PIECE::WhiteBishop => board.white_bishops,
-            PIECE::WhiteRook => board.white_rooks,
-            PIECE::WhiteQueen => board.white_queens,
-            PIECE::WhiteKing => board.white_king,
-
-            PIECE::BlackPawn => board.black_pawns,
-            PIECE::BlackKnight => board.black_knights,
-            PIECE::BlackBishop => board.black_bishops,
-            PIECE::BlackRook => board.black_rooks,
-            PIECE::BlackQueen => board.black_queens,
-            PIECE::BlackKing => board.black_king,
-
-            PIECE::Empty => Bitboard::default(),
-        }
-    }
-
     pub fn is_white(&self) -> bool {
         self.color() == Some(COLOR::WHITE)
     }
@@ -133,6 +130,12 @@ impl PIECE {
         !self.is_empty()
     }
 
+    // 0..12, white pieces first in piece type order, for tables indexed by piece
+    pub fn index(&self) -> usize {
+        let color = self.color().expect("Empty piece has no index");
+        color.index() * 6 + self.piece_type().index()
+    }
+
     pub fn piece_type(&self) -> PieceType {
         match self {
             PIECE::WhitePawn | PIECE::BlackPawn => PieceType::PAWN,
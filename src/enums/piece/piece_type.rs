@@ -8,6 +8,15 @@ pub const PROMOTION_OPTIONS: [PieceType; 4] = [
     PieceType::QUEEN,
 ];
 
+pub const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::PAWN,
+    PieceType::KNIGHT,
+    PieceType::BISHOP,
+    PieceType::ROOK,
+    PieceType::QUEEN,
+    PieceType::KING,
+];
+
 #[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
 pub enum PieceType {
     PAWN,
@@ -20,6 +29,48 @@ pub enum PieceType {
 }
 
 impl PieceType {
+    pub fn index(&self) -> usize {
+        match self {
+            PieceType::PAWN => 0,
+            PieceType::KNIGHT => 1,
+            PieceType::BISHOP => 2,
+            PieceType::ROOK => 3,
+            PieceType::QUEEN => 4,
+            PieceType::KING => 5,
+            PieceType::EMPTY => panic!("Empty piece has no index"),
+        }
+    }
+
+    // material value in centipawns, the king is priced so that it is never traded
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::PAWN => 100,
+            PieceType::KNIGHT => 320,
+            PieceType::BISHOP => 330,
+            PieceType::ROOK => 500,
+            PieceType::QUEEN => 900,
+            PieceType::KING => 20000,
+            PieceType::EMPTY => 0,
+        }
+    }
+
+    // letter used for the piece in FEN and SAN, in either case when parsing
+    pub fn to_char(self) -> char {
+        match self {
+            PieceType::PAWN => 'P',
+            PieceType::KNIGHT => 'N',
+            PieceType::BISHOP => 'B',
+            PieceType::ROOK => 'R',
+            PieceType::QUEEN => 'Q',
+            PieceType::KING => 'K',
+            PieceType::EMPTY => ' ',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<PieceType> {
+        PIECE::from_char(c).map(|piece| piece.piece_type())
+    }
+
     pub fn of_color(&self, color: COLOR) -> PIECE {
         match color {
             COLOR::WHITE => match self {
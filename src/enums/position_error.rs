@@ -0,0 +1,61 @@
+use crate::enums::*;
+use std::fmt;
+
+// reasons a position can't be loaded or could never come up in a game, see Board::validate
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionError {
+    // the FEN string itself is malformed
+    InvalidFen(String),
+    // each side needs exactly one king
+    KingCount(COLOR, u32),
+    TooManyPieces(COLOR, u32),
+    TooManyPawns(COLOR, u32),
+    // more extra queens, rooks, bishops and knights than missing pawns could have promoted to
+    TooManyPromotions(COLOR),
+    PawnOnBackRank(SQUARE),
+    // the side that just moved would have left its own king in check
+    OpponentInCheck,
+    // no single move can uncover more than two checks at once
+    TooManyCheckers(u32),
+    // the king or rook of a castling right has left its starting square
+    InvalidCastlingRights(CASTLE),
+    // no pawn can have just double pushed over this square
+    InvalidEnPassant(SQUARE),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::InvalidFen(reason) => write!(f, "invalid FEN: {}", reason),
+            PositionError::KingCount(color, count) => {
+                write!(f, "{} has {} kings", color, count)
+            }
+            PositionError::TooManyPieces(color, count) => {
+                write!(f, "{} has {} pieces", color, count)
+            }
+            PositionError::TooManyPawns(color, count) => {
+                write!(f, "{} has {} pawns", color, count)
+            }
+            PositionError::TooManyPromotions(color) => {
+                write!(f, "{} has more promoted pieces than missing pawns", color)
+            }
+            PositionError::PawnOnBackRank(square) => {
+                write!(f, "pawn on back rank at {}", square.to_fen())
+            }
+            PositionError::OpponentInCheck => write!(f, "side not to move is in check"),
+            PositionError::TooManyCheckers(count) => {
+                write!(f, "side to move is checked by {} pieces", count)
+            }
+            PositionError::InvalidCastlingRights(castle) => {
+                write!(
+                    f,
+                    "{} castling right without king and rook in place",
+                    castle
+                )
+            }
+            PositionError::InvalidEnPassant(square) => {
+                write!(f, "no pawn can be taken en passant on {}", square.to_fen())
+            }
+        }
+    }
+}
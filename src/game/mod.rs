@@ -0,0 +1,353 @@
+use crate::board::{Board, STARTING_FEN};
+use crate::enums::*;
+use crate::lookup_table::LookupTable;
+use crate::pgn::PgnGame;
+use crate::r#move::Move;
+
+// A game from its starting position on, shared as the state model by the UCI handler, PGN
+// import/export and any UI. Every position reached is kept, so undoing a move, redoing it or
+// jumping to any ply is just moving an index, and repetitions can be found by comparing keys.
+// Moves after the current ply stay around for redo until a different move is pushed
+#[derive(Debug, Clone)]
+pub struct Game<'a> {
+    // positions[0] is the starting position and positions[i + 1] the one after moves[i]
+    positions: Vec<Board<'a>>,
+    moves: Vec<Move>,
+    ply: usize,
+    result: GameResult,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(start: Board<'a>) -> Game<'a> {
+        let mut game = Game {
+            positions: vec![start],
+            moves: Vec::new(),
+            ply: 0,
+            result: GameResult::Ongoing,
+        };
+        game.update_result();
+        game
+    }
+
+    pub fn starting_position(lookup_table: &'a LookupTable) -> Game<'a> {
+        Game::new(Board::from_fen(STARTING_FEN, lookup_table))
+    }
+
+    // ----- state -----
+
+    pub fn board(&self) -> &Board<'a> {
+        &self.positions[self.ply]
+    }
+
+    pub fn start(&self) -> &Board<'a> {
+        &self.positions[0]
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    // moves played up to the current ply
+    pub fn moves(&self) -> &[Move] {
+        &self.moves[..self.ply]
+    }
+
+    // position keys from the start up to and including the current position
+    pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.positions[..=self.ply].iter().map(|board| board.key)
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    // for results the board can't decide, like resignations, time forfeits or agreed draws.
+    // Moving through the game decides the result again
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+    }
+
+    // ----- moving -----
+
+    // plays a legal move at the current ply. Any undone moves are dropped, unless this is the
+    // next of them, in which case it is simply redone
+    pub fn push(&mut self, move_: Move) -> Result<(), MoveError> {
+        let board = self.board();
+        board.validate_move(move_).map_err(MoveError::Illegal)?;
+
+        if self.moves.get(self.ply) == Some(&move_) {
+            self.ply += 1;
+            self.update_result();
+            return Ok(());
+        }
+
+        let mut next = board.clone();
+        next.make_move(move_);
+
+        self.moves.truncate(self.ply);
+        self.positions.truncate(self.ply + 1);
+        self.moves.push(move_);
+        self.positions.push(next);
+        self.ply += 1;
+        self.update_result();
+
+        Ok(())
+    }
+
+    pub fn push_uci(&mut self, uci: &str) -> Result<Move, MoveError> {
+        let move_ = self.board().parse_uci_move(uci)?;
+        self.push(move_)?;
+        Ok(move_)
+    }
+
+    pub fn push_san(&mut self, san: &str) -> Result<Move, MoveError> {
+        let move_ = self
+            .board()
+            .parse_san(san)
+            .ok_or_else(|| MoveError::InvalidFormat(san.to_string()))?;
+        self.push(move_)?;
+        Ok(move_)
+    }
+
+    // undoes the last move, keeping it for redo
+    pub fn pop(&mut self) -> Option<Move> {
+        if self.ply == 0 {
+            return None;
+        }
+
+        self.ply -= 1;
+        self.update_result();
+        Some(self.moves[self.ply])
+    }
+
+    pub fn redo(&mut self) -> Option<Move> {
+        let move_ = *self.moves.get(self.ply)?;
+
+        self.ply += 1;
+        self.update_result();
+        Some(move_)
+    }
+
+    // jumps to any ply between the start and the last known move, returning whether it exists
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.moves.len() {
+            return false;
+        }
+
+        self.ply = ply;
+        self.update_result();
+        true
+    }
+
+    // ----- adjudication -----
+
+    // how many times the current position has come up so far, counting itself
+    pub fn repetitions(&self) -> usize {
+        let key = self.board().key;
+        self.keys().filter(|other| *other == key).count()
+    }
+
+    // how the game ended at the current ply without anyone claiming anything: whatever the board
+    // can tell on its own, or a fivefold repetition
+    pub fn adjudicate(&self) -> Option<ENDGAME> {
+        match self.board().endgame() {
+            Some(endgame) => Some(endgame),
+            None if self.repetitions() >= 5 => Some(ENDGAME::FivefoldRepetition),
+            None => None,
+        }
+    }
+
+    // the draw the side to move could claim right now, unless the board already ended the game.
+    // A result recorded some other way, like an agreed draw, doesn't stop the claim being there
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        if self.adjudicate().is_some() {
+            return None;
+        }
+
+        match self.board().claimable_draw() {
+            Some(claim) => Some(claim),
+            None if self.repetitions() >= 3 => Some(DrawClaim::ThreefoldRepetition),
+            None => None,
+        }
+    }
+
+    // the draw the side to move could claim by announcing a move that leads to it, which FIDE
+    // allows without playing the move on the board
+    pub fn claimable_draw_after(&self, move_: Move) -> Option<DrawClaim> {
+        let board = self.board();
+        if self.adjudicate().is_some() || !board.is_pseudo_legal(move_) || !board.is_legal(move_) {
+            return None;
+        }
+
+        let mut next = board.clone();
+        next.make_move(move_);
+
+        let repetitions = 1 + self.keys().filter(|key| *key == next.key).count();
+        match next.claimable_draw() {
+            Some(claim) => Some(claim),
+            None if repetitions >= 3 => Some(DrawClaim::ThreefoldRepetition),
+            None => None,
+        }
+    }
+
+    // ends the game in a draw if it is still going and the side to move has one to claim
+    pub fn claim_draw(&mut self) -> Option<DrawClaim> {
+        if self.result != GameResult::Ongoing {
+            return None;
+        }
+
+        let claim = self.claimable_draw()?;
+        self.result = GameResult::Draw;
+        Some(claim)
+    }
+
+    fn update_result(&mut self) {
+        self.result = match self.adjudicate() {
+            Some(endgame) => GameResult::from_endgame(endgame, self.board().to_move),
+            None => GameResult::Ongoing,
+        };
+    }
+
+    // ----- pgn -----
+
+    // the main line of a PGN game, with its result if the moves don't decide it
+    pub fn from_pgn(pgn: &PgnGame, lookup_table: &'a LookupTable) -> Result<Game<'a>, MoveError> {
+        let mut game = Game::new(pgn.start_board(lookup_table));
+        for move_ in pgn.mainline() {
+            game.push(move_)?;
+        }
+
+        if game.result == GameResult::Ongoing {
+            game.result = pgn.result;
+        }
+        Ok(game)
+    }
+
+    pub fn to_pgn(&self) -> PgnGame {
+        let mut pgn = PgnGame::new(&self.start().to_fen());
+        pgn.chess960 = self.start().chess960;
+        pgn.result = self.result;
+
+        let mut node = pgn.root();
+        for move_ in self.moves() {
+            node = pgn.add_move(node, *move_);
+        }
+        pgn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &str) {
+        for uci in moves.split_whitespace() {
+            game.push_uci(uci).unwrap();
+        }
+    }
+
+    // the knights go out and back, bringing the position before them back
+    const SHUFFLE: &str = "g1f3 g8f6 f3g1 f6g8";
+
+    #[test]
+    fn push_pop_and_redo() {
+        let lookup_table = LookupTable::new();
+        let mut game = Game::starting_position(&lookup_table);
+        play(&mut game, "e2e4 e7e5 g1f3");
+        let after_e5 = game.positions[2].key;
+
+        assert_eq!(
+            game.pop().map(|m| m.to_uci(false)),
+            Some("g1f3".to_string())
+        );
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.board().key, after_e5);
+
+        assert_eq!(
+            game.redo().map(|m| m.to_uci(false)),
+            Some("g1f3".to_string())
+        );
+        assert_eq!(game.ply(), 3);
+        assert_eq!(game.redo(), None);
+
+        // pushing the undone move again keeps the moves after it
+        game.pop();
+        game.pop();
+        play(&mut game, "e7e5");
+        assert_eq!(game.ply(), 2);
+        assert!(game.redo().is_some());
+
+        // and a different one drops them
+        game.pop();
+        game.pop();
+        play(&mut game, "c7c5");
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.redo(), None);
+
+        assert!(game.push_uci("e2e4").is_err());
+        assert_eq!(game.ply(), 2);
+    }
+
+    #[test]
+    fn set_result_holds_until_the_game_moves() {
+        let lookup_table = LookupTable::new();
+        let mut game = Game::starting_position(&lookup_table);
+        play(&mut game, "e2e4");
+
+        // a resignation stands, until moving through the game decides the result again
+        game.set_result(GameResult::WhiteWins);
+        assert_eq!(game.result(), GameResult::WhiteWins);
+        game.pop();
+        assert_eq!(game.result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn go_to_any_known_ply() {
+        let lookup_table = LookupTable::new();
+        let mut game = Game::starting_position(&lookup_table);
+        play(&mut game, "e2e4 e7e5 g1f3");
+
+        assert!(game.go_to(0));
+        assert_eq!(game.board().key, game.start().key);
+        assert!(game.moves().is_empty());
+
+        assert!(game.go_to(3));
+        assert_eq!(game.moves().len(), 3);
+        assert!(!game.go_to(4));
+        assert_eq!(game.ply(), 3);
+    }
+
+    #[test]
+    fn threefold_is_claimed_and_fivefold_adjudicated() {
+        let lookup_table = LookupTable::new();
+        let mut game = Game::starting_position(&lookup_table);
+
+        // the last move of the second shuffle can be announced to claim the third repetition
+        play(&mut game, &format!("{} g1f3 g8f6 f3g1", SHUFFLE));
+        let back = game.board().parse_uci_move("f6g8").unwrap();
+        assert_eq!(game.claimable_draw(), None);
+        assert_eq!(
+            game.claimable_draw_after(back),
+            Some(DrawClaim::ThreefoldRepetition)
+        );
+
+        play(&mut game, "f6g8");
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.claimable_draw(), Some(DrawClaim::ThreefoldRepetition));
+        assert_eq!(game.adjudicate(), None);
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        // nobody claimed, so the game goes on until the fifth time
+        play(&mut game, &format!("{} {}", SHUFFLE, SHUFFLE));
+        assert_eq!(game.repetitions(), 5);
+        assert_eq!(game.adjudicate(), Some(ENDGAME::FivefoldRepetition));
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(game.claimable_draw(), None);
+
+        // going back undoes the adjudication, and a claim there ends the game
+        game.go_to(8);
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert_eq!(game.claim_draw(), Some(DrawClaim::ThreefoldRepetition));
+        assert_eq!(game.result(), GameResult::Draw);
+    }
+}
@@ -45,6 +45,19 @@ impl Generator {
         Bitboard::new(targets)
     }
 
+    pub fn generate_pawn_attacks(square: SQUARE, color: COLOR) -> Bitboard {
+        let source = square.bits();
+
+        let targets = match color {
+            COLOR::WHITE => vec![north_west(source), north_east(source)],
+            COLOR::BLACK => vec![south_west(source), south_east(source)],
+        };
+
+        let targets = targets.into_iter().fold(0, |acc, x| acc | x.unwrap_or(0));
+
+        Bitboard::new(targets)
+    }
+
     // ---------------------------------------------------
     // ------------------ LEAPING MOVES ------------------
     // ---------------------------------------------------
@@ -189,4 +202,117 @@ impl Generator {
 
         Bitboard::new(targets)
     }
+
+    // ---------------------------------------------------
+    // -------------------- GEOMETRY ---------------------
+    // ---------------------------------------------------
+
+    // squares strictly between a and b if they share a rank, file or diagonal, otherwise empty
+    pub fn generate_between(a: SQUARE, b: SQUARE) -> Bitboard {
+        if a == b {
+            return Bitboard::default();
+        }
+
+        // a slider on a with only b blocking (and vice versa) sees exactly the squares between them
+        let rook_between = Generator::generate_rook_moves(a, Bitboard::new(b.bits()))
+            & Generator::generate_rook_moves(b, Bitboard::new(a.bits()));
+        let bishop_between = Generator::generate_bishop_moves(a, Bitboard::new(b.bits()))
+            & Generator::generate_bishop_moves(b, Bitboard::new(a.bits()));
+
+        match Generator::aligned(a, b) {
+            Some(PieceType::ROOK) => rook_between,
+            Some(PieceType::BISHOP) => bishop_between,
+            _ => Bitboard::default(),
+        }
+    }
+
+    // the full edge-to-edge line through a and b (including both), otherwise empty
+    pub fn generate_line(a: SQUARE, b: SQUARE) -> Bitboard {
+        if a == b {
+            return Bitboard::default();
+        }
+
+        let empty = Bitboard::default();
+        let endpoints = Bitboard::new(a.bits() | b.bits());
+
+        match Generator::aligned(a, b) {
+            Some(PieceType::ROOK) => {
+                (Generator::generate_rook_moves(a, empty)
+                    & Generator::generate_rook_moves(b, empty))
+                    | endpoints
+            }
+            Some(PieceType::BISHOP) => {
+                (Generator::generate_bishop_moves(a, empty)
+                    & Generator::generate_bishop_moves(b, empty))
+                    | endpoints
+            }
+            _ => Bitboard::default(),
+        }
+    }
+
+    // which slider (rook or bishop) connects the two squares on an empty board, if any
+    fn aligned(a: SQUARE, b: SQUARE) -> Option<PieceType> {
+        let empty = Bitboard::default();
+
+        if Generator::generate_rook_moves(a, empty).is_set(b.index()) {
+            Some(PieceType::ROOK)
+        } else if Generator::generate_bishop_moves(a, empty).is_set(b.index()) {
+            Some(PieceType::BISHOP)
+        } else {
+            None
+        }
+    }
+
+    // number of king moves between the squares
+    pub fn generate_chebyshev_distance(a: SQUARE, b: SQUARE) -> u8 {
+        let rank_diff = (a.rank() - b.rank()).unsigned_abs();
+        let file_diff = (a.file() - b.file()).unsigned_abs();
+
+        rank_diff.max(file_diff)
+    }
+
+    // number of rook steps between the squares
+    pub fn generate_manhattan_distance(a: SQUARE, b: SQUARE) -> u8 {
+        let rank_diff = (a.rank() - b.rank()).unsigned_abs();
+        let file_diff = (a.file() - b.file()).unsigned_abs();
+
+        rank_diff + file_diff
+    }
+
+    // the king square plus every square the king attacks
+    pub fn generate_king_zone(square: SQUARE) -> Bitboard {
+        Generator::generate_king_moves(square) | Bitboard::new(square.bits())
+    }
+
+    pub fn generate_adjacent_files(file: FILE) -> Bitboard {
+        let west = match file {
+            FILE::FileA => 0,
+            _ => file.bits() >> 1,
+        };
+        let east = match file {
+            FILE::FileH => 0,
+            _ => file.bits() << 1,
+        };
+
+        Bitboard::new(west | east)
+    }
+
+    // squares in front of the pawn on its own and adjacent files - if no enemy pawn is on them the pawn is passed
+    pub fn generate_passed_pawn_mask(square: SQUARE, color: COLOR) -> Bitboard {
+        let files = square.file().bits() | Generator::generate_adjacent_files(square.file()).bits();
+
+        // every rank strictly in front of the pawn from the pawn's point of view
+        let ahead = match color {
+            COLOR::WHITE => match square.rank() {
+                RANK::Rank8 => 0,
+                _ => !0u64 << ((square.rank().index() + 1) * 8),
+            },
+            COLOR::BLACK => match square.rank() {
+                RANK::Rank1 => 0,
+                _ => !0u64 >> ((8 - square.rank().index()) * 8),
+            },
+        };
+
+        Bitboard::new(files & ahead)
+    }
 }
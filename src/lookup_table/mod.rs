@@ -3,7 +3,8 @@ mod generator;
 use crate::bitboard::*;
 use crate::enums::*;
 use generator::*;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::ser::SerializeStruct;
 use serde::Deserialize;
 use serde::Serialize;
@@ -18,10 +19,20 @@ pub struct LookupTable {
     pub rooks: Vec<Vec<u64>>,
     pub kings: Vec<u64>,
 
+    pub pawn_attacks: Vec<Vec<u64>>,
+    pub between: Vec<Vec<u64>>,
+    pub lines: Vec<Vec<u64>>,
+    pub distances: Vec<Vec<u8>>,
+    pub manhattan_distances: Vec<Vec<u8>>,
+    pub king_zones: Vec<u64>,
+    pub adjacent_files: Vec<u64>,
+    pub passed_pawn_masks: Vec<Vec<u64>>,
+
     pub bishop_magic_numbers: Vec<u64>,
     pub rook_magic_numbers: Vec<u64>,
 
-    pub rng: rand::rngs::ThreadRng,
+    // a thread's own rng can't be shared, and boards on every search thread share this table
+    pub rng: StdRng,
 }
 
 // todo try reducing bishop to 2048 (11 bits) through skipping bishop square on one of the diagonals
@@ -42,12 +53,22 @@ impl LookupTable {
             rooks: vec![vec![0; 4096]; 64],
             kings: vec![0; 64],
 
+            pawn_attacks: vec![vec![0; 64]; 2],
+            between: vec![vec![0; 64]; 64],
+            lines: vec![vec![0; 64]; 64],
+            distances: vec![vec![0; 64]; 64],
+            manhattan_distances: vec![vec![0; 64]; 64],
+            king_zones: vec![0; 64],
+            adjacent_files: vec![0; 8],
+            passed_pawn_masks: vec![vec![0; 64]; 2],
+
             bishop_magic_numbers: vec![0; 64],
             rook_magic_numbers: vec![0; 64],
 
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         };
         table.build_moves();
+        table.build_geometry();
         println!("done in {} milliseconds", start.elapsed().as_millis());
         table.save("lookup_table.bin");
         table
@@ -56,10 +77,8 @@ impl LookupTable {
     pub fn load() -> Option<LookupTable> {
         let path = "lookup_table.bin";
         match std::fs::read(path) {
-            Ok(data) => {
-                let table: LookupTable = bincode::deserialize(&data).unwrap();
-                Some(table)
-            }
+            // a cache written by an older version won't deserialize, so rebuild it instead
+            Ok(data) => bincode::deserialize(&data).ok(),
             Err(_) => None,
         }
     }
@@ -79,6 +98,22 @@ impl LookupTable {
         }
     }
 
+    pub fn build_geometry(&mut self) {
+        for square in SQUARE::iter() {
+            self.build_pawn_attacks(square);
+            self.build_king_zone(square);
+            self.build_passed_pawn_masks(square);
+
+            for other in SQUARE::iter() {
+                self.build_square_pair(square, other);
+            }
+        }
+
+        for file in FILE::iter() {
+            self.adjacent_files[file.index()] = Generator::generate_adjacent_files(file).bits();
+        }
+    }
+
     // --------------------------------------------
     // -------------- MOVE LOOKUP -----------------
     // --------------------------------------------
@@ -145,6 +180,51 @@ impl LookupTable {
             | self.get_rook_moves(square, color, board_occupancy)
     }
 
+    // --------------------------------------------
+    // ------------- GEOMETRY LOOKUP --------------
+    // --------------------------------------------
+
+    // squares a pawn of the given color on square attacks (captures only, no pushes)
+    pub fn get_pawn_attacks(&self, square: SQUARE, color: COLOR) -> Bitboard {
+        Bitboard::new(self.pawn_attacks[color.index()][square.index()])
+    }
+
+    // squares strictly between a and b, empty if they don't share a line
+    pub fn get_between(&self, a: SQUARE, b: SQUARE) -> Bitboard {
+        Bitboard::new(self.between[a.index()][b.index()])
+    }
+
+    // whole edge-to-edge line through a and b, empty if they don't share a line
+    pub fn get_line(&self, a: SQUARE, b: SQUARE) -> Bitboard {
+        Bitboard::new(self.lines[a.index()][b.index()])
+    }
+
+    pub fn aligned(&self, a: SQUARE, b: SQUARE, c: SQUARE) -> bool {
+        self.get_line(a, b).is_set(c.index())
+    }
+
+    // chebyshev distance, i.e. number of king moves from a to b
+    pub fn get_distance(&self, a: SQUARE, b: SQUARE) -> u8 {
+        self.distances[a.index()][b.index()]
+    }
+
+    pub fn get_manhattan_distance(&self, a: SQUARE, b: SQUARE) -> u8 {
+        self.manhattan_distances[a.index()][b.index()]
+    }
+
+    pub fn get_king_zone(&self, square: SQUARE) -> Bitboard {
+        Bitboard::new(self.king_zones[square.index()])
+    }
+
+    pub fn get_adjacent_files(&self, square: SQUARE) -> Bitboard {
+        Bitboard::new(self.adjacent_files[square.file().index()])
+    }
+
+    // squares that must be free of enemy pawns for a pawn on square to be passed
+    pub fn get_passed_pawn_mask(&self, square: SQUARE, color: COLOR) -> Bitboard {
+        Bitboard::new(self.passed_pawn_masks[color.index()][square.index()])
+    }
+
     // --------------------------------------------
     // ---------- OCCUPANCY TABLES ----------------
     // --------------------------------------------
@@ -351,6 +431,37 @@ impl LookupTable {
         self.knights[square.index()] = moves.bits();
     }
 
+    // --------------------------------------------
+    // ---------------- GEOMETRY ------------------
+    // --------------------------------------------
+
+    pub fn build_pawn_attacks(&mut self, square: SQUARE) {
+        for color in COLOR::iter() {
+            let attacks = Generator::generate_pawn_attacks(square, color);
+            self.pawn_attacks[color.index()][square.index()] = attacks.bits();
+        }
+    }
+
+    pub fn build_king_zone(&mut self, square: SQUARE) {
+        self.king_zones[square.index()] = Generator::generate_king_zone(square).bits();
+    }
+
+    pub fn build_passed_pawn_masks(&mut self, square: SQUARE) {
+        for color in COLOR::iter() {
+            let mask = Generator::generate_passed_pawn_mask(square, color);
+            self.passed_pawn_masks[color.index()][square.index()] = mask.bits();
+        }
+    }
+
+    pub fn build_square_pair(&mut self, a: SQUARE, b: SQUARE) {
+        let (i, j) = (a.index(), b.index());
+
+        self.between[i][j] = Generator::generate_between(a, b).bits();
+        self.lines[i][j] = Generator::generate_line(a, b).bits();
+        self.distances[i][j] = Generator::generate_chebyshev_distance(a, b);
+        self.manhattan_distances[i][j] = Generator::generate_manhattan_distance(a, b);
+    }
+
     // --------------------------------------------
     // ------------- SLIDING MOVES ----------------
     // --------------------------------------------
@@ -396,12 +507,20 @@ impl Serialize for LookupTable {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("LookupTable", 7)?;
+        let mut state = serializer.serialize_struct("LookupTable", 15)?;
         state.serialize_field("pawns", &self.pawns)?;
         state.serialize_field("knights", &self.knights)?;
         state.serialize_field("bishops", &self.bishops)?;
         state.serialize_field("rooks", &self.rooks)?;
         state.serialize_field("kings", &self.kings)?;
+        state.serialize_field("pawn_attacks", &self.pawn_attacks)?;
+        state.serialize_field("between", &self.between)?;
+        state.serialize_field("lines", &self.lines)?;
+        state.serialize_field("distances", &self.distances)?;
+        state.serialize_field("manhattan_distances", &self.manhattan_distances)?;
+        state.serialize_field("king_zones", &self.king_zones)?;
+        state.serialize_field("adjacent_files", &self.adjacent_files)?;
+        state.serialize_field("passed_pawn_masks", &self.passed_pawn_masks)?;
         state.serialize_field("bishop_magic_numbers", &self.bishop_magic_numbers)?;
         state.serialize_field("rook_magic_numbers", &self.rook_magic_numbers)?;
         state.end()
@@ -441,12 +560,36 @@ impl<'de> Deserialize<'de> for LookupTable {
                 let kings = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(4, &self))?;
-                let bishop_magic_numbers = seq
+                let pawn_attacks = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(5, &self))?;
-                let rook_magic_numbers = seq
+                let between = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(6, &self))?;
+                let lines = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(7, &self))?;
+                let distances = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(8, &self))?;
+                let manhattan_distances = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(9, &self))?;
+                let king_zones = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(10, &self))?;
+                let adjacent_files = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(11, &self))?;
+                let passed_pawn_masks = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(12, &self))?;
+                let bishop_magic_numbers = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(13, &self))?;
+                let rook_magic_numbers = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(14, &self))?;
 
                 Ok(LookupTable {
                     pawns,
@@ -454,9 +597,17 @@ impl<'de> Deserialize<'de> for LookupTable {
                     bishops,
                     rooks,
                     kings,
+                    pawn_attacks,
+                    between,
+                    lines,
+                    distances,
+                    manhattan_distances,
+                    king_zones,
+                    adjacent_files,
+                    passed_pawn_masks,
                     bishop_magic_numbers,
                     rook_magic_numbers,
-                    rng: rand::thread_rng(),
+                    rng: StdRng::from_entropy(),
                 })
             }
         }
@@ -469,6 +620,14 @@ impl<'de> Deserialize<'de> for LookupTable {
                 "bishops",
                 "rooks",
                 "kings",
+                "pawn_attacks",
+                "between",
+                "lines",
+                "distances",
+                "manhattan_distances",
+                "king_zones",
+                "adjacent_files",
+                "passed_pawn_masks",
                 "bishop_magic_numbers",
                 "rook_magic_numbers",
             ],
@@ -476,3 +635,57 @@ impl<'de> Deserialize<'de> for LookupTable {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SQUARE::*;
+
+    fn squares(squares: &[SQUARE]) -> Bitboard {
+        Bitboard::new(squares.iter().fold(0, |bits, square| bits | square.bits()))
+    }
+
+    #[test]
+    fn between() {
+        let lookup_table = LookupTable::new();
+        assert_eq!(
+            lookup_table.get_between(A1, H8),
+            squares(&[B2, C3, D4, E5, F6, G7])
+        );
+        assert_eq!(
+            lookup_table.get_between(H8, A1),
+            lookup_table.get_between(A1, H8)
+        );
+        assert_eq!(lookup_table.get_between(E1, E4), squares(&[E2, E3]));
+        assert_eq!(lookup_table.get_between(A5, D5), squares(&[B5, C5]));
+        assert!(lookup_table.get_between(E4, E5).empty());
+        assert!(lookup_table.get_between(E4, E4).empty());
+    }
+
+    #[test]
+    fn line() {
+        let lookup_table = LookupTable::new();
+        assert_eq!(
+            lookup_table.get_line(B2, G7),
+            squares(&[A1, B2, C3, D4, E5, F6, G7, H8])
+        );
+        assert_eq!(lookup_table.get_line(C1, B2), squares(&[C1, B2, A3]));
+        assert_eq!(
+            lookup_table.get_line(D3, D6),
+            squares(&[D1, D2, D3, D4, D5, D6, D7, D8])
+        );
+        assert!(lookup_table.aligned(B2, G7, H8));
+        assert!(!lookup_table.aligned(B2, G7, H7));
+    }
+
+    #[test]
+    fn squares_off_any_line_are_empty() {
+        let lookup_table = LookupTable::new();
+        // a knight's move, and further off
+        for (a, b) in [(A1, B3), (E4, F6), (A1, H7), (C2, H4)] {
+            assert!(lookup_table.get_between(a, b).empty());
+            assert!(lookup_table.get_line(a, b).empty());
+            assert!(!lookup_table.aligned(a, b, H8));
+        }
+    }
+}
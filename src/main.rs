@@ -2,12 +2,18 @@ mod args;
 mod bitboard;
 mod board;
 mod enums;
+mod game;
 mod helpers;
 mod lookup_table;
 mod r#move;
 mod perft;
+mod pgn;
+mod problem;
+mod search;
+mod uci;
 
 use args::*;
+use search::SearchConfig;
 
 pub fn time_fn<T>(body: impl FnOnce() -> T) -> (T, f64) {
     let start_time = std::time::Instant::now();
@@ -26,6 +32,29 @@ fn main() {
     }
 
     match args.mode {
-        EngineMode::PERFT => perft::run_perft_tests(args.depth),
+        EngineMode::PERFT => perft::run_perft_tests(args.depth, args.staged),
+        EngineMode::BENCH => {
+            let mut config = SearchConfig::default();
+            for heuristic in args.disable {
+                config.disable(heuristic);
+            }
+            search::run_bench(args.depth as i32, args.threads, config)
+        }
+        EngineMode::UCI => uci::run_uci(),
+        EngineMode::MATE => {
+            search::run_mate_solver(&args.fen, args.depth as u32, args.pgn_out.as_deref())
+        }
+        EngineMode::PROBLEM => problem::run_problem(&args.fen, &args.stipulation, &args.twin),
+        EngineMode::PGN => match &args.pgn {
+            Some(path) => pgn::run_pgn(path, args.pgn_out.as_deref()),
+            None => println!("pgn mode reads the file given with --pgn"),
+        },
+        EngineMode::ANALYSE => search::run_analysis(
+            &args.fen,
+            args.depth as i32,
+            args.lines,
+            args.threads,
+            args.pgn_out.as_deref(),
+        ),
     }
 }
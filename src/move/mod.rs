@@ -0,0 +1,112 @@
+mod move_list;
+mod move_picker;
+
+use crate::enums::*;
+use std::fmt::{Display, Formatter};
+
+pub use move_list::*;
+pub use move_picker::*;
+
+// packed into 16 bits: source square (bits 0-5), target square (bits 6-11) and MoveFlag (bits 12-15)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Move {
+    data: u16,
+}
+
+impl Move {
+    // a1a1 can never be a real move, so the all-zero move doubles as an empty slot
+    pub const NULL: Move = Move { data: 0 };
+
+    pub fn new(source: SQUARE, target: SQUARE, flag: MoveFlag) -> Move {
+        Move {
+            data: source.index() as u16 | (target.index() as u16) << 6 | (flag as u16) << 12,
+        }
+    }
+
+    // rebuilds a move from its packed form, e.g. out of a hash table entry
+    pub fn from_bits(data: u16) -> Option<Move> {
+        MoveFlag::from_bits(data >> 12).map(|_| Move { data })
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.data
+    }
+
+    pub fn source(&self) -> SQUARE {
+        SQUARE::from((self.data & 0x3F) as usize)
+    }
+
+    pub fn target(&self) -> SQUARE {
+        SQUARE::from((self.data >> 6 & 0x3F) as usize)
+    }
+
+    pub fn flag(&self) -> MoveFlag {
+        MoveFlag::from_bits(self.data >> 12).expect("Invalid move flag")
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        self.flag().promotion()
+    }
+
+    // castles are always made from the back rank, so the source rank tells us the color
+    pub fn castling(&self) -> Option<CASTLE> {
+        match (self.flag(), self.source().rank()) {
+            (MoveFlag::KingCastle, RANK::Rank1) => Some(CASTLE::WhiteKingside),
+            (MoveFlag::QueenCastle, RANK::Rank1) => Some(CASTLE::WhiteQueenside),
+            (MoveFlag::KingCastle, RANK::Rank8) => Some(CASTLE::BlackKingside),
+            (MoveFlag::QueenCastle, RANK::Rank8) => Some(CASTLE::BlackQueenside),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Move::NULL
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.flag().is_capture()
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flag() == MoveFlag::EnPassant
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flag().is_castle()
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.flag().is_promotion()
+    }
+
+    pub fn is_double_pawn_push(&self) -> bool {
+        self.flag() == MoveFlag::DoublePawnPush
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        !self.is_capture() && !self.is_promotion()
+    }
+
+    // UCI long algebraic notation. Castles are stored as king takes rook, which is how chess960
+    // writes them, while standard chess writes the square the king lands on (e1g1)
+    pub fn to_uci(self, chess960: bool) -> String {
+        let target = match self.castling() {
+            Some(castle) if !chess960 => castle.king_target(),
+            _ => self.target(),
+        };
+
+        let mut out = format!("{}{}", self.source(), target).to_ascii_lowercase();
+
+        if let Some(promotion) = self.promotion() {
+            out.push(promotion.to_char().to_ascii_lowercase());
+        }
+
+        out
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_uci(false))
+    }
+}
@@ -0,0 +1,255 @@
+use crate::board::Board;
+use crate::r#move::*;
+use crate::search::{mvv_lva, MoveOrdering, OrderingConfig, PieceTo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    GenerateEvasions,
+    Evasions,
+    Done,
+}
+
+// Lazily yields pseudo-legal moves in the order search wants to try them:
+// hash move, winning captures, killers, countermove, quiets and finally losing captures.
+// Each batch is only generated once the previous one has been exhausted, so a
+// cutoff on the hash move never pays for move generation at all.
+// When in check only evasions are generated (still hash move first).
+// Quiets are sorted by the search's history tables when it passes them to next_move,
+// iterating without them yields quiets in generation order
+pub struct MovePicker<'b, 'a> {
+    board: &'b Board<'a>,
+    stage: Stage,
+    in_check: bool,
+
+    hash_move: Move,
+    killers: [Move; 2],
+    killer_index: usize,
+    counter_move: Move,
+    previous: [Option<PieceTo>; 2],
+    sort_quiets: bool,
+
+    moves: MoveList,
+    scores: [i32; MAX_MOVES],
+    index: usize,
+    bad_captures: MoveList,
+}
+
+impl<'b, 'a> MovePicker<'b, 'a> {
+    pub fn new(board: &'b Board<'a>, hash_move: Move, killers: [Move; 2]) -> MovePicker<'b, 'a> {
+        MovePicker {
+            board,
+            stage: Stage::HashMove,
+            in_check: board.in_check(board.to_move),
+
+            hash_move,
+            killers,
+            killer_index: 0,
+            counter_move: Move::NULL,
+            previous: [None; 2],
+            sort_quiets: false,
+
+            moves: MoveList::new(),
+            scores: [0; MAX_MOVES],
+            index: 0,
+            bad_captures: MoveList::new(),
+        }
+    }
+
+    // the quiet that refuted the previous move, and the previous two moves for continuation
+    // history (the last one first)
+    pub fn with_history(mut self, counter_move: Move, previous: [Option<PieceTo>; 2]) -> Self {
+        self.counter_move = counter_move;
+        self.previous = previous;
+        self
+    }
+
+    fn score_moves(&mut self, score: impl Fn(&Self, Move) -> i32) {
+        for i in 0..self.moves.len() {
+            self.scores[i] = score(self, self.moves[i]);
+        }
+        self.index = 0;
+    }
+
+    // selection sort step: swap the best remaining move to the front and return it
+    fn pick_best(&mut self) -> Option<Move> {
+        if self.index >= self.moves.len() {
+            return None;
+        }
+
+        let mut best = self.index;
+        for i in self.index + 1..self.moves.len() {
+            if self.scores[i] > self.scores[best] {
+                best = i;
+            }
+        }
+
+        self.moves.swap(self.index, best);
+        self.scores.swap(self.index, best);
+        self.index += 1;
+
+        Some(self.moves[self.index - 1])
+    }
+
+    fn is_killer(&self, m: Move) -> bool {
+        self.killers.contains(&m)
+    }
+
+    // killers and the countermove are tried before the other quiets, so they're skipped there
+    fn is_refutation(&self, m: Move) -> bool {
+        m == self.hash_move || self.is_killer(m) || m == self.counter_move
+    }
+
+    // the next move to try, ordered by the search's tables if it has any
+    pub fn next_move(&mut self, ordering: Option<&MoveOrdering>) -> Option<Move> {
+        let color = self.board.to_move;
+        let config = ordering.map_or(OrderingConfig::default(), |ordering| ordering.config);
+
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = match self.in_check {
+                        true => Stage::GenerateEvasions,
+                        false => Stage::GenerateCaptures,
+                    };
+
+                    if self.board.is_pseudo_legal(self.hash_move) {
+                        return Some(self.hash_move);
+                    }
+                }
+
+                Stage::GenerateCaptures => {
+                    self.moves = self.board.generate_captures(color);
+                    self.score_moves(|picker, m| match config.mvv_lva {
+                        true => mvv_lva(picker.board, m),
+                        false => 0,
+                    });
+                    self.stage = Stage::GoodCaptures;
+                }
+
+                Stage::GoodCaptures => match self.pick_best() {
+                    Some(m) if m == self.hash_move => {}
+                    Some(m) if config.see && self.board.see(m) < 0 => self.bad_captures.push(m),
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Killers,
+                },
+
+                Stage::Killers => {
+                    if self.killer_index >= self.killers.len() {
+                        self.stage = Stage::CounterMove;
+                        continue;
+                    }
+
+                    let killer = self.killers[self.killer_index];
+                    self.killer_index += 1;
+
+                    let is_duplicate = self.killers[..self.killer_index - 1].contains(&killer);
+                    if killer != self.hash_move
+                        && !is_duplicate
+                        && killer.is_quiet()
+                        && self.board.is_pseudo_legal(killer)
+                    {
+                        return Some(killer);
+                    }
+                }
+
+                Stage::CounterMove => {
+                    self.stage = Stage::GenerateQuiets;
+
+                    let counter = self.counter_move;
+                    if counter != self.hash_move
+                        && !self.is_killer(counter)
+                        && counter.is_quiet()
+                        && self.board.is_pseudo_legal(counter)
+                    {
+                        return Some(counter);
+                    }
+                }
+
+                Stage::GenerateQuiets => {
+                    self.moves = self.board.generate_quiets(color);
+                    self.index = 0;
+                    self.stage = Stage::Quiets;
+
+                    if let Some(ordering) = ordering.filter(|ordering| ordering.scores_quiets()) {
+                        let previous = self.previous;
+                        self.score_moves(|picker, m| {
+                            ordering.quiet_score(picker.board, m, &previous)
+                        });
+                        self.sort_quiets = true;
+                    }
+                }
+
+                Stage::Quiets => {
+                    let next = match self.sort_quiets {
+                        true => self.pick_best(),
+                        false => {
+                            self.index += 1;
+                            self.moves.as_slice().get(self.index - 1).copied()
+                        }
+                    };
+
+                    match next {
+                        Some(m) if self.is_refutation(m) => {}
+                        Some(m) => return Some(m),
+                        None => {
+                            self.moves = self.bad_captures;
+                            self.index = 0;
+                            self.stage = Stage::BadCaptures;
+                        }
+                    }
+                }
+
+                Stage::BadCaptures => {
+                    if self.index >= self.moves.len() {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+
+                    self.index += 1;
+                    return Some(self.moves[self.index - 1]);
+                }
+
+                // captures of the checker first, then the quiets by history
+                Stage::GenerateEvasions => {
+                    self.moves = self.board.generate_evasions(color);
+                    let previous = self.previous;
+                    self.score_moves(|picker, m| {
+                        match (m.is_capture() || m.is_promotion(), ordering) {
+                            (true, _) if config.mvv_lva => (1 << 20) + mvv_lva(picker.board, m),
+                            (true, _) => 1 << 20,
+                            (false, Some(ordering)) => {
+                                ordering.quiet_score(picker.board, m, &previous)
+                            }
+                            (false, None) => 0,
+                        }
+                    });
+                    self.stage = Stage::Evasions;
+                }
+
+                Stage::Evasions => match self.pick_best() {
+                    Some(m) if m == self.hash_move => {}
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Done,
+                },
+
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+impl Iterator for MovePicker<'_, '_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        self.next_move(None)
+    }
+}
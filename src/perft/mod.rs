@@ -40,7 +40,7 @@ pub fn load_perft_table(path: &str) -> HashMap<String, Vec<Option<u64>>> {
     map
 }
 
-pub fn run_perft_tests(depth: u8) {
+pub fn run_perft_tests(depth: u8, staged: bool) {
     let lookup_table = LookupTable::new();
     let perft_table = load_perft_table("perft.csv");
 
@@ -65,9 +65,11 @@ pub fn run_perft_tests(depth: u8) {
         let _promo = row.1.get(4).copied().flatten();
         let _checks = row.1.get(5).copied().flatten();
         let _checkmates = row.1.get(6).copied().flatten();
+        // rows with nothing but nodes don't need the moves of the last ply played out
+        let node_only = row.1.iter().skip(1).all(Option::is_none);
 
         let (elapsed, nodes, captures, enp, castles, promo, checks, checkmates) =
-            get_perft_result_for_fen(fen.clone(), depth, &lookup_table);
+            get_perft_result_for_fen(fen.clone(), depth, node_only, staged, &lookup_table);
 
         println!("{}", "-".repeat(80));
         println!("{}\nDepth = {}", fen, depth);
@@ -158,13 +160,15 @@ pub fn run_perft_tests(depth: u8) {
 pub fn get_perft_result_for_fen(
     fen: String,
     depth: u8,
+    node_only: bool,
+    staged: bool,
     lookup_table: &LookupTable,
 ) -> (f64, u64, u64, u64, u64, u64, u64, u64) {
     let mut board = Board::from_fen(&fen, &lookup_table);
     let start = std::time::Instant::now();
 
     let (nodes, captures, enpassants, castles, promotions, checks, checkmates) =
-        board.perft(depth, depth, false);
+        board.perft(depth, depth, node_only, staged);
 
     let elapsed = start.elapsed().as_secs_f64();
 
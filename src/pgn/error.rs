@@ -0,0 +1,44 @@
+use crate::enums::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnErrorKind {
+    Io(String),
+    InvalidTag(String),
+    InvalidFen(Vec<PositionError>),
+    // SAN that doesn't match exactly one legal move
+    IllegalMove(String),
+    UnexpectedToken(String),
+    UnclosedComment,
+    UnclosedVariation,
+    UnmatchedParenthesis,
+}
+
+// a game that couldn't be read, the reader carries on with the next one
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnError {
+    // 1-based index of the game in the file and line the problem was found on
+    pub game: usize,
+    pub line: usize,
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {}, line {}: ", self.game, self.line)?;
+
+        match &self.kind {
+            PgnErrorKind::Io(error) => write!(f, "{}", error),
+            PgnErrorKind::InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+            PgnErrorKind::InvalidFen(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "invalid FEN tag: {}", errors.join(", "))
+            }
+            PgnErrorKind::IllegalMove(san) => write!(f, "illegal move {}", san),
+            PgnErrorKind::UnexpectedToken(token) => write!(f, "unexpected {}", token),
+            PgnErrorKind::UnclosedComment => write!(f, "comment is never closed"),
+            PgnErrorKind::UnclosedVariation => write!(f, "variation is never closed"),
+            PgnErrorKind::UnmatchedParenthesis => write!(f, "unmatched )"),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::lookup_table::LookupTable;
+use crate::r#move::Move;
+use crate::search::{SearchResult, MATE, MATE_BOUND};
+use std::time::Duration;
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PgnScore {
+    // from white's point of view
+    Centipawns(i32),
+    // moves until mate, negative when black mates
+    Mate(i32),
+}
+
+// an engine evaluation stored in a comment as [%eval 0.35] or [%eval #-3,24]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PgnEval {
+    pub score: PgnScore,
+    pub depth: Option<u8>,
+}
+
+impl PgnEval {
+    // a search's score, which is for the side to move, turned around to white's point of view
+    pub fn from_search(result: &SearchResult, to_move: COLOR) -> PgnEval {
+        let score = match result.score {
+            s if s >= MATE_BOUND => PgnScore::Mate((MATE - s + 1) / 2),
+            s if s <= -MATE_BOUND => PgnScore::Mate(-(MATE + s) / 2),
+            s => PgnScore::Centipawns(s),
+        };
+        let score = match (to_move, score) {
+            (COLOR::WHITE, score) => score,
+            (COLOR::BLACK, PgnScore::Centipawns(centipawns)) => PgnScore::Centipawns(-centipawns),
+            (COLOR::BLACK, PgnScore::Mate(moves)) => PgnScore::Mate(-moves),
+        };
+
+        PgnEval {
+            score,
+            depth: Some(result.depth.clamp(0, u8::MAX as i32) as u8),
+        }
+    }
+}
+
+// a move in the game tree. The root node holds the starting position and a null move
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnNode {
+    pub move_: Move,
+    pub parent: Option<usize>,
+    // the first child continues the line this node is on, the others are variations
+    pub children: Vec<usize>,
+
+    // comment written before the first move of a variation
+    pub starting_comment: Option<String>,
+    // comment written after the move, without any [%...] commands
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+    pub clock: Option<Duration>,
+    pub eval: Option<PgnEval>,
+}
+
+impl PgnNode {
+    fn new(move_: Move, parent: Option<usize>) -> PgnNode {
+        PgnNode {
+            move_,
+            parent,
+            children: Vec::new(),
+            starting_comment: None,
+            comment: None,
+            nags: Vec::new(),
+            clock: None,
+            eval: None,
+        }
+    }
+}
+
+// A game with all its variations. Nodes live in one Vec and point at each other by index,
+// nodes[0] being the root. Positions aren't stored, they are replayed from the start when needed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnGame {
+    // in the order they were read or added
+    pub tags: Vec<(String, String)>,
+    pub start_fen: String,
+    pub chess960: bool,
+    pub nodes: Vec<PgnNode>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    pub fn new(start_fen: &str) -> PgnGame {
+        PgnGame {
+            tags: Vec::new(),
+            start_fen: start_fen.to_string(),
+            chess960: false,
+            nodes: vec![PgnNode::new(Move::NULL, None)],
+            result: GameResult::Ongoing,
+        }
+    }
+
+    pub fn root(&self) -> usize {
+        0
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // adds a move after parent (as its main line if it is the first) and returns the new node.
+    // The move isn't checked, it has to be legal in the position at parent
+    pub fn add_move(&mut self, parent: usize, move_: Move) -> usize {
+        if let Some(&existing) = self.nodes[parent]
+            .children
+            .iter()
+            .find(|&&child| self.nodes[child].move_ == move_)
+        {
+            return existing;
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(PgnNode::new(move_, Some(parent)));
+        self.nodes[parent].children.push(node);
+        node
+    }
+
+    // Adds the lines of a MultiPV search of the position at node, best first, so the best
+    // becomes the main line unless node already has one and the others variations. The first
+    // move of each line carries its evaluation
+    pub fn add_analysis(
+        &mut self,
+        node: usize,
+        lines: &[SearchResult],
+        lookup_table: &LookupTable,
+    ) {
+        let to_move = self.board_at(node, lookup_table).to_move;
+
+        for line in lines {
+            let mut pv = line.pv.iter();
+            let Some(&first) = pv.next() else { continue };
+
+            let first = self.add_move(node, first);
+            self.nodes[first].eval = Some(PgnEval::from_search(line, to_move));
+            pv.fold(first, |parent, &move_| self.add_move(parent, move_));
+        }
+    }
+
+    // nodes of the main line after the root
+    pub fn mainline_nodes(&self) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        let mut node = self.root();
+        while let Some(&child) = self.nodes[node].children.first() {
+            nodes.push(child);
+            node = child;
+        }
+        nodes
+    }
+
+    pub fn mainline(&self) -> Vec<Move> {
+        self.mainline_nodes()
+            .into_iter()
+            .map(|node| self.nodes[node].move_)
+            .collect()
+    }
+
+    // moves leading from the starting position to node
+    pub fn moves_to(&self, node: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut node = node;
+        while let Some(parent) = self.nodes[node].parent {
+            moves.push(self.nodes[node].move_);
+            node = parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    pub fn start_board<'a>(&self, lookup_table: &'a LookupTable) -> Board<'a> {
+        let mut board = Board::from_fen(&self.start_fen, lookup_table);
+        board.chess960 |= self.chess960;
+        board
+    }
+
+    // position after the move at node
+    pub fn board_at<'a>(&self, node: usize, lookup_table: &'a LookupTable) -> Board<'a> {
+        let mut board = self.start_board(lookup_table);
+        for move_ in self.moves_to(node) {
+            board.make_move(move_);
+        }
+        board
+    }
+}
@@ -0,0 +1,80 @@
+mod error;
+mod game;
+mod reader;
+mod tokenizer;
+mod writer;
+
+pub use error::*;
+pub use game::*;
+pub use reader::*;
+
+use crate::game::Game;
+use crate::lookup_table::LookupTable;
+use std::fs::File;
+use std::io::BufWriter;
+
+// Reads every game of a PGN file and replays its main line, reporting the games that can't be
+// read and carrying on past them. The games read are written back out in export format if out
+// is given
+pub fn run_pgn(path: &str, out: Option<&str>) {
+    let lookup_table = LookupTable::new();
+    let reader = match PgnReader::from_file(path, &lookup_table) {
+        Ok(reader) => reader,
+        Err(error) => {
+            println!("can't open {}: {}", path, error);
+            return;
+        }
+    };
+    let mut out = match out.map(File::create).transpose() {
+        Ok(out) => out.map(BufWriter::new),
+        Err(error) => {
+            println!("can't create {}: {}", out.unwrap_or_default(), error);
+            return;
+        }
+    };
+
+    let mut games = 0;
+    let mut failed = 0;
+    for (i, pgn) in reader.enumerate() {
+        games += 1;
+
+        let game = pgn.map_err(|error| error.to_string()).and_then(|pgn| {
+            match Game::from_pgn(&pgn, &lookup_table) {
+                Ok(game) => Ok((pgn, game)),
+                Err(error) => Err(format!("game {}: {}", i + 1, error)),
+            }
+        });
+
+        match game {
+            Ok((pgn, game)) => {
+                // how the game ended on the board, or the draw that was there to claim
+                let ending = game
+                    .adjudicate()
+                    .or(game.claimable_draw().map(|claim| claim.endgame()))
+                    .map_or(String::new(), |ending| format!(" ({})", ending));
+                println!(
+                    "game {}: {} - {}, {} plies, {}{}",
+                    i + 1,
+                    pgn.tag("White").unwrap_or("?"),
+                    pgn.tag("Black").unwrap_or("?"),
+                    game.ply(),
+                    game.result().to_pgn(),
+                    ending
+                );
+
+                if let Some(out) = out.as_mut() {
+                    if let Err(error) = pgn.write_pgn(out, &lookup_table) {
+                        println!("can't write game {}: {}", i + 1, error);
+                        return;
+                    }
+                }
+            }
+            Err(error) => {
+                failed += 1;
+                println!("{}", error);
+            }
+        }
+    }
+
+    println!("{} games read, {} with errors", games, failed);
+}
@@ -0,0 +1,474 @@
+use crate::board::{Board, STARTING_FEN};
+use crate::enums::*;
+use crate::lookup_table::LookupTable;
+use crate::pgn::tokenizer::*;
+use crate::pgn::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+// Reads games one at a time from any buffered source, so a database never has to fit in memory.
+// A game that fails to parse comes out as an error and reading carries on with the next one
+pub struct PgnReader<'a, R: BufRead> {
+    source: R,
+    lookup_table: &'a LookupTable,
+    line_number: usize,
+    games_read: usize,
+    // first line of the next game, read while looking for the end of the previous one
+    pending_line: Option<String>,
+    failed: bool,
+}
+
+impl<'a> PgnReader<'a, BufReader<File>> {
+    pub fn from_file(path: &str, lookup_table: &'a LookupTable) -> std::io::Result<Self> {
+        Ok(PgnReader::new(
+            BufReader::new(File::open(path)?),
+            lookup_table,
+        ))
+    }
+}
+
+impl<'a, R: BufRead> PgnReader<'a, R> {
+    pub fn new(source: R, lookup_table: &'a LookupTable) -> Self {
+        PgnReader {
+            source,
+            lookup_table,
+            line_number: 0,
+            games_read: 0,
+            pending_line: None,
+            failed: false,
+        }
+    }
+
+    fn read_line(&mut self) -> std::io::Result<Option<String>> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+
+        let mut line = String::new();
+        match self.source.read_line(&mut line)? {
+            0 => Ok(None),
+            _ => {
+                self.line_number += 1;
+                Ok(Some(line))
+            }
+        }
+    }
+
+    // Collects the raw text of the next game and the line it starts on. A game ends where the
+    // next tag section starts or right after a result that closes its movetext
+    fn read_game_text(&mut self) -> std::io::Result<Option<(String, usize)>> {
+        let mut text = String::new();
+        let mut first_line = self.line_number + 1;
+
+        let mut in_comment = false;
+        let mut variation_depth = 0;
+        let mut in_movetext = false;
+
+        while let Some(line) = self.read_line()? {
+            let trimmed = line.trim();
+
+            if text.is_empty() {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                // a pending line was already counted, the game starts on it
+                first_line = self.line_number;
+            }
+
+            let is_tag = !in_comment && trimmed.starts_with('[');
+            if is_tag && in_movetext {
+                self.pending_line = Some(line);
+                break;
+            }
+            text.push_str(&line);
+
+            if is_tag || line.starts_with('%') {
+                continue;
+            }
+
+            // follow comments and variations to know whether a result really ends the game
+            let mut word = String::new();
+            let mut last_word = String::new();
+            for c in line.chars() {
+                if in_comment {
+                    in_comment = c != '}';
+                    continue;
+                }
+
+                match c {
+                    '{' => in_comment = true,
+                    ';' => break,
+                    '(' => variation_depth += 1,
+                    ')' => variation_depth -= 1,
+                    _ => {}
+                }
+
+                match c.is_whitespace() || "{}();".contains(c) {
+                    true if !word.is_empty() => last_word = std::mem::take(&mut word),
+                    true => {}
+                    false => {
+                        word.push(c);
+                        in_movetext = true;
+                    }
+                }
+            }
+            if !word.is_empty() {
+                last_word = word;
+            }
+
+            if !in_comment && variation_depth <= 0 && GameResult::from_pgn(&last_word).is_some() {
+                break;
+            }
+        }
+
+        match text.trim().is_empty() {
+            true => Ok(None),
+            false => Ok(Some((text, first_line))),
+        }
+    }
+
+    fn parse_game(&self, text: &str, first_line: usize) -> Result<PgnGame, PgnError> {
+        let error = |line: usize, kind: PgnErrorKind| PgnError {
+            game: self.games_read,
+            line,
+            kind,
+        };
+
+        let tokens = tokenize(text, first_line).map_err(|(line, kind)| error(line, kind))?;
+        let mut tokens = tokens.into_iter().peekable();
+
+        // 1. Tag pairs and the position they describe
+        let mut tags = Vec::new();
+        while let Some((Token::Tag(name, value), _)) = tokens.peek() {
+            tags.push((name.clone(), value.clone()));
+            tokens.next();
+        }
+
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let fen = tag("FEN").unwrap_or(STARTING_FEN);
+        let chess960 = tag("Variant").is_some_and(|variant| {
+            let variant = variant.to_ascii_lowercase();
+            variant.contains("960") || variant.contains("fischer")
+        });
+
+        let start = Board::from_fen_strict(fen, chess960, self.lookup_table)
+            .map_err(|errors| error(first_line, PgnErrorKind::InvalidFen(errors)))?;
+
+        let mut game = PgnGame::new(&start.to_fen());
+        game.chess960 = start.chess960;
+        game.result = tag("Result")
+            .and_then(GameResult::from_pgn)
+            .unwrap_or(GameResult::Ongoing);
+        game.tags = tags.clone();
+
+        // 2. Movetext, replayed on a board per node so that SAN can be resolved in variations
+        let mut boards = vec![start];
+        let mut node = game.root();
+        let mut variations = Vec::new();
+        let mut starting_comment: Option<String> = None;
+        let mut variation_start = false;
+
+        for (token, line) in tokens {
+            match token {
+                Token::San(san) => {
+                    let move_ = boards[node]
+                        .parse_san(&san)
+                        .ok_or_else(|| error(line, PgnErrorKind::IllegalMove(san)))?;
+
+                    let mut board = boards[node].clone();
+                    board.make_move(move_);
+
+                    let child = game.add_move(node, move_);
+                    if child == boards.len() {
+                        boards.push(board);
+                    }
+                    node = child;
+
+                    if let Some(comment) = starting_comment.take() {
+                        game.nodes[node].starting_comment = Some(comment);
+                    }
+                    variation_start = false;
+                }
+
+                Token::Comment(comment) => match variation_start {
+                    true => append_comment(&mut starting_comment, &comment),
+                    false => add_comment(&mut game.nodes[node], &comment),
+                },
+
+                Token::Nag(nag) => game.nodes[node].nags.push(nag),
+
+                // a variation replaces the move just played, so it starts from that move's parent
+                Token::StartVariation => {
+                    let parent = game.nodes[node].parent.ok_or_else(|| {
+                        error(line, PgnErrorKind::UnexpectedToken(String::from("(")))
+                    })?;
+                    variations.push(node);
+                    node = parent;
+                    variation_start = true;
+                }
+
+                Token::EndVariation => {
+                    node = variations
+                        .pop()
+                        .ok_or_else(|| error(line, PgnErrorKind::UnmatchedParenthesis))?;
+                    variation_start = false;
+                }
+
+                Token::Result(result) => {
+                    if !variations.is_empty() {
+                        return Err(error(line, PgnErrorKind::UnclosedVariation));
+                    }
+                    game.result = result;
+                    break;
+                }
+
+                Token::Tag(name, _) => {
+                    return Err(error(line, PgnErrorKind::UnexpectedToken(name)));
+                }
+            }
+        }
+
+        if !variations.is_empty() {
+            return Err(error(first_line, PgnErrorKind::UnclosedVariation));
+        }
+
+        Ok(game)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<'_, R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let (text, first_line) = match self.read_game_text() {
+            Ok(Some(game)) => game,
+            Ok(None) => return None,
+            Err(io_error) => {
+                self.failed = true;
+                return Some(Err(PgnError {
+                    game: self.games_read + 1,
+                    line: self.line_number,
+                    kind: PgnErrorKind::Io(io_error.to_string()),
+                }));
+            }
+        };
+
+        self.games_read += 1;
+        Some(self.parse_game(&text, first_line))
+    }
+}
+
+fn append_comment(comment: &mut Option<String>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match comment {
+        Some(comment) => {
+            comment.push(' ');
+            comment.push_str(text);
+        }
+        None => *comment = Some(text.to_string()),
+    }
+}
+
+// stores a comment on node, pulling [%clk] and [%eval] commands out into their own fields
+fn add_comment(node: &mut PgnNode, comment: &str) {
+    let mut text = String::new();
+    let mut rest = comment;
+
+    while let Some(start) = rest.find("[%") {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+        text.push_str(&rest[..start]);
+
+        let command = &rest[start + 2..end];
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let understood = match name {
+            "clk" => parse_clock(argument.trim()).map(|clock| node.clock = Some(clock)),
+            "eval" => parse_eval(argument.trim()).map(|eval| node.eval = Some(eval)),
+            _ => None,
+        };
+        if understood.is_none() {
+            text.push_str(&rest[start..=end]);
+        }
+
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    append_comment(
+        &mut node.comment,
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .as_str(),
+    );
+}
+
+// h:mm:ss with optional fractions of a second
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    for part in clock.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    match seconds >= 0.0 {
+        true => Some(Duration::from_secs_f64(seconds)),
+        false => None,
+    }
+}
+
+// pawns (0.35) or mate in n (#-3), optionally followed by the search depth (0.35,24)
+fn parse_eval(eval: &str) -> Option<PgnEval> {
+    let (score, depth) = match eval.split_once(',') {
+        Some((score, depth)) => (score, Some(depth.trim().parse().ok()?)),
+        None => (eval, None),
+    };
+
+    let score = match score.strip_prefix('#') {
+        Some(mate) => PgnScore::Mate(mate.parse().ok()?),
+        None => PgnScore::Centipawns((score.parse::<f64>().ok()? * 100.0).round() as i32),
+    };
+
+    Some(PgnEval { score, depth })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGN: &str = r#"[Event "Test"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 (1... c5 $1 {Sicilian} 2. Nf3 (2. c3 d5) d6) 2. Nf3!? Nc6 {[%clk 0:05:00] [%eval 0.35,20]} 3. Bb5 1-0
+
+[Event "Broken"]
+[Result "*"]
+
+1. e4 e5 2. Ke3 *
+
+[Event "After"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+"#;
+
+    fn read(lookup_table: &LookupTable) -> Vec<Result<PgnGame, PgnError>> {
+        PgnReader::new(PGN.as_bytes(), lookup_table).collect()
+    }
+
+    // the SAN of the move that led to node
+    fn san(game: &PgnGame, node: usize, lookup_table: &LookupTable) -> String {
+        let parent = game.nodes[node].parent.unwrap();
+        game.board_at(parent, lookup_table)
+            .to_san(game.nodes[node].move_)
+    }
+
+    fn sans(game: &PgnGame, nodes: &[usize], lookup_table: &LookupTable) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|node| san(game, *node, lookup_table))
+            .collect()
+    }
+
+    #[test]
+    fn tags_and_mainline() {
+        let lookup_table = LookupTable::new();
+        let game = read(&lookup_table).remove(0).unwrap();
+
+        assert_eq!(game.tag("Event"), Some("Test"));
+        assert_eq!(game.tag("White"), Some("Alice"));
+        assert_eq!(game.result, GameResult::WhiteWins);
+        assert_eq!(
+            sans(&game, &game.mainline_nodes(), &lookup_table),
+            vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]
+        );
+    }
+
+    #[test]
+    fn comments_nags_and_commands() {
+        let lookup_table = LookupTable::new();
+        let game = read(&lookup_table).remove(0).unwrap();
+        let mainline = game.mainline_nodes();
+
+        assert_eq!(
+            game.nodes[mainline[0]].comment.as_deref(),
+            Some("best by test")
+        );
+        assert_eq!(game.nodes[mainline[2]].nags, vec![5]);
+
+        // the commands are taken out of the comment, which is left empty
+        let knight = &game.nodes[mainline[3]];
+        assert_eq!(knight.comment, None);
+        assert_eq!(knight.clock, Some(Duration::from_secs(300)));
+        assert_eq!(
+            knight.eval,
+            Some(PgnEval {
+                score: PgnScore::Centipawns(35),
+                depth: Some(20),
+            })
+        );
+    }
+
+    #[test]
+    fn nested_variations() {
+        let lookup_table = LookupTable::new();
+        let game = read(&lookup_table).remove(0).unwrap();
+        let e4 = game.mainline_nodes()[0];
+
+        assert_eq!(
+            sans(&game, &game.nodes[e4].children, &lookup_table),
+            vec!["e5", "c5"]
+        );
+
+        let c5 = game.nodes[e4].children[1];
+        assert_eq!(game.nodes[c5].nags, vec![1]);
+        assert_eq!(game.nodes[c5].comment.as_deref(), Some("Sicilian"));
+        assert_eq!(
+            sans(&game, &game.nodes[c5].children, &lookup_table),
+            vec!["Nf3", "c3"]
+        );
+
+        let c3 = game.nodes[c5].children[1];
+        assert_eq!(
+            sans(&game, &game.nodes[c3].children, &lookup_table),
+            vec!["d5"]
+        );
+    }
+
+    #[test]
+    fn bad_game_in_the_middle() {
+        let lookup_table = LookupTable::new();
+        let games = read(&lookup_table);
+        assert_eq!(games.len(), 3);
+
+        assert_eq!(
+            games[1],
+            Err(PgnError {
+                game: 2,
+                line: 11,
+                kind: PgnErrorKind::IllegalMove(String::from("Ke3")),
+            })
+        );
+
+        // reading carries on after it
+        let after = games[2].as_ref().unwrap();
+        assert_eq!(after.tag("Event"), Some("After"));
+        assert_eq!(after.result, GameResult::BlackWins);
+        assert_eq!(after.mainline().len(), 4);
+    }
+}
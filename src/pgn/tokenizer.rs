@@ -0,0 +1,217 @@
+use crate::enums::*;
+use crate::pgn::PgnErrorKind;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Tag(String, String),
+    Comment(String),
+    StartVariation,
+    EndVariation,
+    Nag(u8),
+    San(String),
+    Result(GameResult),
+}
+
+// move suffixes and the NAGs they stand for
+const SUFFIX_NAGS: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+
+struct Tokenizer<'t> {
+    chars: Peekable<Chars<'t>>,
+    line: usize,
+    at_line_start: bool,
+}
+
+impl Tokenizer<'_> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.at_line_start = c == '\n';
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    // consumes characters up to and including end, returning everything before it
+    fn read_until(&mut self, end: char) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            match self.next_char()? {
+                c if c == end => return Some(text),
+                c => text.push(c),
+            }
+        }
+    }
+
+    // [Name "value"] with \" and \\ escapes inside the value
+    fn read_tag(&mut self) -> Result<Token, PgnErrorKind> {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            name.push(c);
+            self.next_char();
+        }
+
+        while self.chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+            self.next_char();
+        }
+
+        let mut value = String::new();
+        let quoted = self.next_char() == Some('"');
+        if quoted {
+            loop {
+                match self.next_char() {
+                    Some('\\') => match self.next_char() {
+                        Some(c) => value.push(c),
+                        None => break,
+                    },
+                    Some('"') => break,
+                    Some('\n') | None => return Err(PgnErrorKind::InvalidTag(name)),
+                    Some(c) => value.push(c),
+                }
+            }
+        }
+
+        let rest = self.read_until(']');
+        match (quoted, rest) {
+            (true, Some(rest)) if !name.is_empty() && rest.trim().is_empty() => {
+                Ok(Token::Tag(name, value))
+            }
+            _ => Err(PgnErrorKind::InvalidTag(name)),
+        }
+    }
+
+    // a word of movetext: a move number, a move with suffixes or a result
+    fn read_word(&mut self, tokens: &mut Vec<(Token, usize)>) -> Result<(), PgnErrorKind> {
+        let line = self.line;
+        let mut word = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || "{}();[$".contains(c) {
+                break;
+            }
+            word.push(c);
+            self.next_char();
+        }
+
+        if let Some(result) = GameResult::from_pgn(&word) {
+            tokens.push((Token::Result(result), line));
+            return Ok(());
+        }
+
+        // 1. Move numbers, possibly glued to the move (1.e4, 12...Nf6)
+        let mut san = word.as_str();
+        if san.starts_with(|c: char| c.is_ascii_digit()) && !san.starts_with("0-0") {
+            let number_end = san.find(|c: char| !c.is_ascii_digit()).unwrap_or(san.len());
+            san = san[number_end..].trim_start_matches('.');
+        }
+        if san.is_empty() {
+            return Ok(());
+        }
+
+        // 2. Trailing !? suffixes become NAGs
+        let move_end = san.trim_end_matches(['!', '?']).len();
+        let (san, suffix) = san.split_at(move_end);
+
+        if !san.is_empty() {
+            tokens.push((Token::San(san.to_string()), line));
+        }
+        if !suffix.is_empty() {
+            match SUFFIX_NAGS.iter().find(|(s, _)| *s == suffix) {
+                Some((_, nag)) => tokens.push((Token::Nag(*nag), line)),
+                None => return Err(PgnErrorKind::UnexpectedToken(word)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Splits the text of one game into tokens tagged with their line number. On failure
+// returns the line the problem was found on
+pub(crate) fn tokenize(
+    text: &str,
+    first_line: usize,
+) -> Result<Vec<(Token, usize)>, (usize, PgnErrorKind)> {
+    let mut tokenizer = Tokenizer {
+        chars: text.chars().peekable(),
+        line: first_line,
+        at_line_start: true,
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(&c) = tokenizer.chars.peek() {
+        let line = tokenizer.line;
+
+        // lines starting with % are escaped and skipped entirely
+        if c == '%' && tokenizer.at_line_start {
+            tokenizer.read_until('\n');
+            continue;
+        }
+
+        if c.is_whitespace() {
+            tokenizer.next_char();
+            continue;
+        }
+
+        match c {
+            '[' => {
+                tokenizer.next_char();
+                let tag = tokenizer.read_tag().map_err(|kind| (line, kind))?;
+                tokens.push((tag, line));
+            }
+            '{' => {
+                tokenizer.next_char();
+                let comment = tokenizer
+                    .read_until('}')
+                    .ok_or((line, PgnErrorKind::UnclosedComment))?;
+                tokens.push((Token::Comment(comment.trim().to_string()), line));
+            }
+            ';' => {
+                tokenizer.next_char();
+                let comment = tokenizer.read_until('\n').unwrap_or_default();
+                tokens.push((Token::Comment(comment.trim().to_string()), line));
+            }
+            '(' => {
+                tokenizer.next_char();
+                tokens.push((Token::StartVariation, line));
+            }
+            ')' => {
+                tokenizer.next_char();
+                tokens.push((Token::EndVariation, line));
+            }
+            '$' => {
+                tokenizer.next_char();
+                let mut digits = String::new();
+                while let Some(&c) = tokenizer.chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    digits.push(c);
+                    tokenizer.next_char();
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| (line, PgnErrorKind::UnexpectedToken(format!("${}", digits))))?;
+                tokens.push((Token::Nag(nag), line));
+            }
+            '}' | ']' => {
+                return Err((line, PgnErrorKind::UnexpectedToken(c.to_string())));
+            }
+            _ => tokenizer
+                .read_word(&mut tokens)
+                .map_err(|kind| (line, kind))?,
+        }
+    }
+
+    Ok(tokens)
+}
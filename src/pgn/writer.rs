@@ -0,0 +1,294 @@
+use crate::board::{Board, STARTING_FEN};
+use crate::enums::*;
+use crate::lookup_table::LookupTable;
+use crate::pgn::*;
+use std::io::Write;
+use std::time::Duration;
+
+const LINE_WIDTH: usize = 80;
+
+impl PgnGame {
+    // the result decided by the position at the end of the main line, or the stored one (a
+    // resignation, time forfeit or unfinished game) if the game isn't over there
+    pub fn adjudicated_result(&self, lookup_table: &LookupTable) -> GameResult {
+        let last = self.mainline_nodes().last().copied().unwrap_or(self.root());
+        let board = self.board_at(last, lookup_table);
+
+        match board.endgame() {
+            Some(endgame) => GameResult::from_endgame(endgame, board.to_move),
+            None => self.result,
+        }
+    }
+
+    pub fn write_pgn<W: Write>(
+        &self,
+        out: &mut W,
+        lookup_table: &LookupTable,
+    ) -> std::io::Result<()> {
+        write!(out, "{}", self.to_pgn(lookup_table))
+    }
+
+    // Export format: the seven tag roster first, then any other tags, then SAN movetext wrapped
+    // at 80 columns and terminated by the result
+    pub fn to_pgn(&self, lookup_table: &LookupTable) -> String {
+        let mut pgn = String::new();
+        let result = self.adjudicated_result(lookup_table);
+
+        // 1. Tags
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => result.to_pgn(),
+                "Date" => self.tag(name).unwrap_or("????.??.??"),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            pgn.push_str(&format_tag(name, value));
+        }
+
+        let setup_tags = ["SetUp", "FEN", "Variant"];
+        if self.start_fen != STARTING_FEN || self.chess960 {
+            pgn.push_str(&format_tag("SetUp", "1"));
+            pgn.push_str(&format_tag("FEN", &self.start_fen));
+        }
+        if self.chess960 {
+            pgn.push_str(&format_tag("Variant", "Chess960"));
+        }
+
+        for (name, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && !setup_tags.contains(&name.as_str()) {
+                pgn.push_str(&format_tag(name, value));
+            }
+        }
+        pgn.push('\n');
+
+        // 2. Movetext
+        let mut words = Vec::new();
+        let root = &self.nodes[self.root()];
+        push_comment(&mut words, root.comment.as_deref(), None, None);
+
+        let board = self.start_board(lookup_table);
+        self.push_line(self.root(), &board, &mut words, true);
+        words.push(result.to_pgn().to_string());
+
+        pgn.push_str(&wrap(&words));
+        pgn.push_str("\n\n");
+        pgn
+    }
+
+    // Writes the line continuing from node, along with every variation branching off it. Board
+    // is the position at node. Move numbers are repeated for black after anything that interrupts
+    // the moves, like a comment or a variation
+    fn push_line(&self, node: usize, board: &Board, words: &mut Vec<String>, numbered: bool) {
+        let mut node = node;
+        let mut board = board.clone();
+        let mut numbered = numbered;
+
+        while let Some(&main) = self.nodes[node].children.first() {
+            numbered = self.push_move(main, &board, words, numbered);
+
+            for &variation in self.nodes[node].children.iter().skip(1) {
+                let mut variation_words = Vec::new();
+                let needs_number = self.push_move(variation, &board, &mut variation_words, true);
+
+                let mut variation_board = board.clone();
+                variation_board.make_move(self.nodes[variation].move_);
+                self.push_line(
+                    variation,
+                    &variation_board,
+                    &mut variation_words,
+                    needs_number,
+                );
+
+                variation_words[0].insert(0, '(');
+                variation_words.last_mut().unwrap().push(')');
+                words.append(&mut variation_words);
+                numbered = true;
+            }
+
+            board.make_move(self.nodes[main].move_);
+            node = main;
+        }
+    }
+
+    // writes one move with its number, NAGs and comments, returning whether a comment follows it
+    fn push_move(
+        &self,
+        node: usize,
+        board: &Board,
+        words: &mut Vec<String>,
+        numbered: bool,
+    ) -> bool {
+        let pgn_node = &self.nodes[node];
+        push_comment(words, pgn_node.starting_comment.as_deref(), None, None);
+
+        let san = board.to_san(pgn_node.move_);
+        match (board.to_move, numbered) {
+            (COLOR::WHITE, _) => words.push(format!("{}. {}", board.fullmove_number, san)),
+            (COLOR::BLACK, true) => words.push(format!("{}... {}", board.fullmove_number, san)),
+            (COLOR::BLACK, false) => words.push(san),
+        }
+
+        for nag in pgn_node.nags.iter() {
+            words.push(format!("${}", nag));
+        }
+
+        push_comment(
+            words,
+            pgn_node.comment.as_deref(),
+            pgn_node.eval,
+            pgn_node.clock,
+        )
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+// a {comment} followed by [%eval] and [%clk] commands, split into words so it can be wrapped
+fn push_comment(
+    words: &mut Vec<String>,
+    comment: Option<&str>,
+    eval: Option<PgnEval>,
+    clock: Option<Duration>,
+) -> bool {
+    // braces can't be escaped inside a comment
+    let mut comment_words: Vec<String> = comment
+        .unwrap_or_default()
+        .replace('}', ")")
+        .split_whitespace()
+        .map(|word| word.to_string())
+        .collect();
+
+    if let Some(eval) = eval {
+        let score = match eval.score {
+            PgnScore::Centipawns(centipawns) => format!("{:.2}", centipawns as f64 / 100.0),
+            PgnScore::Mate(moves) => format!("#{}", moves),
+        };
+        let depth = eval
+            .depth
+            .map_or(String::new(), |depth| format!(",{}", depth));
+        comment_words.push(format!("[%eval {}{}]", score, depth));
+    }
+
+    if let Some(clock) = clock {
+        let seconds = clock.as_secs();
+        comment_words.push(format!(
+            "[%clk {}:{:02}:{:02}]",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ));
+    }
+
+    if comment_words.is_empty() {
+        return false;
+    }
+
+    comment_words[0].insert(0, '{');
+    comment_words.last_mut().unwrap().push('}');
+    words.append(&mut comment_words);
+    true
+}
+
+// greedily fills lines up to LINE_WIDTH, a word longer than that gets a line of its own
+fn wrap(words: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for word in words {
+        if line_length > 0 && line_length + 1 + word.len() > LINE_WIDTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(word);
+        line_length += word.len();
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    const FOOLS_MATE: [&str; 4] = ["f2f3", "e7e5", "g2g4", "d8h4"];
+
+    #[test]
+    fn wraps_at_eighty_columns() {
+        let words = |lengths: &[usize]| -> Vec<String> {
+            lengths.iter().map(|length| "x".repeat(*length)).collect()
+        };
+
+        assert_eq!(wrap(&words(&[39, 40])).lines().count(), 1);
+        assert_eq!(wrap(&words(&[39, 41])).lines().count(), 2);
+        // a word too long for any line still gets one of its own
+        assert_eq!(
+            wrap(&words(&[10, 90, 10]))
+                .lines()
+                .map(str::len)
+                .collect::<Vec<_>>(),
+            vec![10, 90, 10]
+        );
+    }
+
+    #[test]
+    fn long_game_round_trips_through_wrapping() {
+        let lookup_table = LookupTable::new();
+        let mut pgn = PgnGame::new(STARTING_FEN);
+        let mut board = pgn.start_board(&lookup_table);
+        let mut node = pgn.root();
+
+        // the first legal move every time, until the game ends or 80 plies are played
+        for _ in 0..80 {
+            let moves = board.generate_moves_for_color(board.to_move);
+            let Some(&m) = moves.iter().next() else { break };
+            node = pgn.add_move(node, m);
+            board.make_move(m);
+        }
+
+        let text = pgn.to_pgn(&lookup_table);
+        let movetext: Vec<&str> = text.lines().skip_while(|line| !line.is_empty()).collect();
+        assert!(movetext.len() > 3);
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+
+        let read: Vec<_> = PgnReader::new(text.as_bytes(), &lookup_table).collect();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].as_ref().unwrap().mainline(), pgn.mainline());
+    }
+
+    #[test]
+    fn setup_tags_only_off_the_starting_position() {
+        let lookup_table = LookupTable::new();
+
+        let standard = PgnGame::new(STARTING_FEN).to_pgn(&lookup_table);
+        assert!(!standard.contains("[SetUp"));
+        assert!(!standard.contains("[FEN"));
+
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        let endgame = PgnGame::new(fen).to_pgn(&lookup_table);
+        assert!(endgame.contains("[SetUp \"1\"]\n"));
+        assert!(endgame.contains(&format!("[FEN \"{}\"]\n", fen)));
+    }
+
+    #[test]
+    fn result_comes_from_the_final_position() {
+        let lookup_table = LookupTable::new();
+        let mut game = Game::starting_position(&lookup_table);
+        for uci in FOOLS_MATE {
+            game.push_uci(uci).unwrap();
+        }
+
+        // the stored result is overruled by the mate on the board
+        let mut pgn = game.to_pgn();
+        pgn.result = GameResult::Ongoing;
+        let text = pgn.to_pgn(&lookup_table);
+
+        assert!(text.contains("[Result \"0-1\"]\n"));
+        assert!(text.ends_with("1. f3 e5 2. g4 Qh4# 0-1\n\n"));
+    }
+}
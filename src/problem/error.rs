@@ -0,0 +1,33 @@
+use crate::enums::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProblemError {
+    InvalidStipulation(String),
+    InvalidTwin(String),
+    InvalidFen(Vec<PositionError>),
+    // a twin whose changes leave an illegal position
+    IllegalTwin(String, Vec<PositionError>),
+}
+
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |errors: &Vec<PositionError>| {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            errors.join(", ")
+        };
+
+        match self {
+            ProblemError::InvalidStipulation(stipulation) => write!(
+                f,
+                "invalid stipulation {}, expected #n, h#n or s#n",
+                stipulation
+            ),
+            ProblemError::InvalidTwin(twin) => write!(f, "invalid twin {}", twin),
+            ProblemError::InvalidFen(errors) => write!(f, "invalid FEN: {}", join(errors)),
+            ProblemError::IllegalTwin(twin, errors) => {
+                write!(f, "twin {} is illegal: {}", twin, join(errors))
+            }
+        }
+    }
+}
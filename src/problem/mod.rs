@@ -0,0 +1,199 @@
+mod error;
+mod solver;
+mod stipulation;
+mod twin;
+
+pub use error::*;
+pub use solver::*;
+pub use stipulation::*;
+pub use twin::*;
+
+use crate::board::Board;
+use crate::lookup_table::LookupTable;
+use crate::r#move::Move;
+use thousands::Separable;
+
+// Solves a composed problem and each of its twins, printing every solution with its cooks and
+// duals. Twins are lettered after the diagram, a), b), c) and so on
+pub fn run_problem(fen: &str, stipulation: &str, twins: &[String]) {
+    if let Err(error) = solve_problem(fen, stipulation, twins) {
+        println!("{}", error);
+    }
+}
+
+fn solve_problem(fen: &str, stipulation: &str, twins: &[String]) -> Result<(), ProblemError> {
+    let lookup_table = LookupTable::new();
+    let stipulation = Stipulation::parse(stipulation)?;
+    let diagram =
+        Board::from_fen_strict(fen, false, &lookup_table).map_err(ProblemError::InvalidFen)?;
+
+    // every twin is checked before anything is solved, so a typo doesn't wait on the solver
+    let mut positions = vec![(String::from("diagram"), diagram.clone())];
+    for twin in twins {
+        let twin = Twin::parse(twin)?;
+        positions.push((twin.to_string(), twin.apply(&diagram)?));
+    }
+
+    let mut solver = ProblemSolver::new(stipulation);
+    for (i, (name, board)) in positions.iter().enumerate() {
+        let letter = (b'a' + i as u8) as char;
+
+        println!("{}", "-".repeat(80));
+        match twins.is_empty() {
+            true => println!("{} {}", stipulation, board.to_fen()),
+            false => println!("{}) {}, {} {}", letter, name, stipulation, board.to_fen()),
+        }
+        println!("{}", "-".repeat(80));
+
+        let start = std::time::Instant::now();
+        let solution = solver.solve(board);
+        let elapsed = start.elapsed().as_secs_f64();
+
+        print_solution(&mut solver, board, &solution);
+        println!(
+            "{} nodes in {:.2} seconds",
+            solution.nodes.separate_with_commas(),
+            elapsed
+        );
+    }
+
+    Ok(())
+}
+
+// a move in problem notation after the given number of plies: "2. Qg7#" or "1... Kh8"
+fn numbered(board: &Board, m: Move, ply: usize) -> String {
+    match ply % 2 {
+        0 => format!("{}. {}", ply / 2 + 1, board.to_san(m)),
+        _ => format!("{}... {}", ply / 2 + 1, board.to_san(m)),
+    }
+}
+
+// a whole line in problem notation, numbered from the first move of the problem
+fn line_notation(board: &Board, line: &[Move]) -> String {
+    let mut board = board.clone();
+    let mut parts = Vec::new();
+
+    for (ply, m) in line.iter().enumerate() {
+        parts.push(match ply % 2 {
+            0 => numbered(&board, *m, ply),
+            _ => board.to_san(*m),
+        });
+        board.make_move(*m);
+    }
+
+    parts.join(" ")
+}
+
+fn print_solution(solver: &mut ProblemSolver, board: &Board, solution: &Solution) {
+    match solution.stipulation.kind {
+        // a helpmate is a list of lines
+        StipulationKind::Helpmate => {
+            for line in solution.lines() {
+                println!("{}", line_notation(board, &line));
+            }
+        }
+        // a directmate or selfmate is a key with a variation for every defence
+        _ => {
+            for key in &solution.tree {
+                let mut after_key = board.clone();
+                after_key.make_move(key.m);
+
+                let threats: Vec<String> = solver
+                    .threats(board, key.m)
+                    .iter()
+                    .map(|threat| {
+                        // threats are played as if the defender passed
+                        let mut passed = after_key.clone();
+                        passed.make_null_move();
+                        numbered(&passed, *threat, 2)
+                    })
+                    .collect();
+
+                match threats.is_empty() {
+                    true => println!("{}!", numbered(board, key.m, 0)),
+                    false => println!(
+                        "{}! threat: {}",
+                        numbered(board, key.m, 0),
+                        threats.join(" / ")
+                    ),
+                }
+                print_defences(&after_key, &key.children, 1);
+            }
+        }
+    }
+
+    // 1. The verdict: a sound problem has one solution and no duals
+    let helpmate = solution.stipulation.kind == StipulationKind::Helpmate;
+    let first_moves: Vec<String> = solution.tree.iter().map(|n| board.to_san(n.m)).collect();
+    match solution.tree.first() {
+        None => println!("No solution"),
+        Some(_) if solution.is_cooked() && helpmate => println!(
+            "Cooked: {} solutions starting {}",
+            solution.lines().len(),
+            first_moves.join(", ")
+        ),
+        Some(_) if solution.is_cooked() => println!(
+            "Cooked: {} keys {}",
+            first_moves.len(),
+            first_moves.join(", ")
+        ),
+        Some(_) if helpmate => println!("Solutions: {}", solution.lines().len()),
+        Some(_) => println!("Key: {}", first_moves[0]),
+    }
+
+    // 2. Duals, with the line that leads to them
+    for dual in solution.duals() {
+        let mut after = board.clone();
+        for m in &dual.line {
+            after.make_move(*m);
+        }
+        let moves: Vec<String> = dual
+            .moves
+            .iter()
+            .map(|m| numbered(&after, *m, dual.line.len()))
+            .collect();
+
+        println!(
+            "Dual after {}: {}",
+            line_notation(board, &dual.line),
+            moves.join(", ")
+        );
+    }
+}
+
+// the defences after an attacker's move, one per line, each with what answers it
+fn print_defences(board: &Board, defences: &[SolutionNode], ply: usize) {
+    let indent = "    ".repeat(ply.div_ceil(2));
+
+    for defence in defences {
+        let mut after = board.clone();
+        after.make_move(defence.m);
+        let defence_text = numbered(board, defence.m, ply);
+
+        match defence.children.as_slice() {
+            // the defence mates, which only ends a selfmate
+            [] => println!("{}{}", indent, defence_text),
+            // a single answer is written on the same line, with what follows it indented below
+            [answer] => {
+                println!(
+                    "{}{} {}",
+                    indent,
+                    defence_text,
+                    numbered(&after, answer.m, ply + 1)
+                );
+                let mut after_answer = after.clone();
+                after_answer.make_move(answer.m);
+                print_defences(&after_answer, &answer.children, ply + 2);
+            }
+            answers => {
+                println!("{}{}", indent, defence_text);
+                for answer in answers {
+                    println!("{}    {}", indent, numbered(&after, answer.m, ply + 1));
+                    let mut after_answer = after.clone();
+                    after_answer.make_move(answer.m);
+                    print_defences(&after_answer, &answer.children, ply + 2);
+                }
+            }
+        }
+    }
+}
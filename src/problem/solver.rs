@@ -0,0 +1,361 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::problem::{Stipulation, StipulationKind};
+use crate::r#move::*;
+use std::collections::HashMap;
+
+// A move of a solution with everything that can follow it. Under a key of a directmate or
+// selfmate come the defences, and under each defence the moves that still work against it. In a
+// helpmate, both sides' moves simply follow each other down every line that works
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionNode {
+    pub m: Move,
+    pub children: Vec<SolutionNode>,
+}
+
+// a choice of moves where a sound problem leaves only one: the line up to it, and the moves
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dual {
+    pub line: Vec<Move>,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub stipulation: Stipulation,
+    // the keys of a directmate or selfmate, the first moves of a helpmate
+    pub tree: Vec<SolutionNode>,
+    pub nodes: u64,
+}
+
+impl Solution {
+    // more than one key, or helpmate lines that already differ on the first move
+    pub fn is_cooked(&self) -> bool {
+        self.tree.len() > 1
+    }
+
+    // every full line of the solution, one per mate
+    pub fn lines(&self) -> Vec<Vec<Move>> {
+        fn walk(nodes: &[SolutionNode], line: &mut Vec<Move>, lines: &mut Vec<Vec<Move>>) {
+            for node in nodes {
+                line.push(node.m);
+                match node.children.is_empty() {
+                    true => lines.push(line.clone()),
+                    false => walk(&node.children, line, lines),
+                }
+                line.pop();
+            }
+        }
+
+        let mut lines = Vec::new();
+        walk(&self.tree, &mut Vec::new(), &mut lines);
+        lines
+    }
+
+    // Every place after the first move where the solving side had more than one way on. In a
+    // directmate or selfmate only the attacker's choices count, as the defender's are meant to be
+    // many; in a helpmate both sides play together, so both sides' choices do
+    pub fn duals(&self) -> Vec<Dual> {
+        fn walk(
+            nodes: &[SolutionNode],
+            helpmate: bool,
+            line: &mut Vec<Move>,
+            duals: &mut Vec<Dual>,
+        ) {
+            for node in nodes {
+                line.push(node.m);
+                // the attacker is to move after an even number of plies
+                let attacker_to_move = line.len().is_multiple_of(2);
+                if node.children.len() > 1 && (helpmate || attacker_to_move) {
+                    duals.push(Dual {
+                        line: line.clone(),
+                        moves: node.children.iter().map(|child| child.m).collect(),
+                    });
+                }
+                walk(&node.children, helpmate, line, duals);
+                line.pop();
+            }
+        }
+
+        let helpmate = self.stipulation.kind == StipulationKind::Helpmate;
+        let mut duals = Vec::new();
+        walk(&self.tree, helpmate, &mut Vec::new(), &mut duals);
+        duals
+    }
+}
+
+// Finds every solution of a problem by trying all moves, which is slow but the only way to be
+// sure a problem has no cooks. Whether a position works is settled first without building
+// anything, and remembered, so the tree is only built along the moves that work
+pub struct ProblemSolver {
+    stipulation: Stipulation,
+    // (key, moves or plies left) to whether the side to move gets there
+    table: HashMap<(u64, u32), bool>,
+    nodes: u64,
+}
+
+impl ProblemSolver {
+    pub fn new(stipulation: Stipulation) -> ProblemSolver {
+        ProblemSolver {
+            stipulation,
+            table: HashMap::new(),
+            nodes: 0,
+        }
+    }
+
+    pub fn solve(&mut self, board: &Board) -> Solution {
+        self.table.clear();
+        self.nodes = 0;
+
+        let moves = self.stipulation.moves;
+        let tree = match self.stipulation.kind {
+            StipulationKind::Helpmate => self.help_tree(board, 2 * moves),
+            _ => self.attack_tree(board, moves),
+        };
+
+        Solution {
+            stipulation: self.stipulation,
+            tree,
+            nodes: self.nodes,
+        }
+    }
+
+    // A directmate's threat: what the attacker would mate with after the key if the defender
+    // could pass. There's none when the key checks, as the defender can't pass out of check
+    pub fn threats(&mut self, board: &Board, key: Move) -> Vec<Move> {
+        let moves = self.stipulation.moves;
+        if self.stipulation.kind != StipulationKind::Directmate || moves < 2 {
+            return Vec::new();
+        }
+
+        let mut board = board.clone();
+        board.make_move(key);
+        if board.in_check(board.to_move) {
+            return Vec::new();
+        }
+        board.make_null_move();
+
+        self.attack_tree(&board, moves - 1)
+            .iter()
+            .map(|node| node.m)
+            .collect()
+    }
+
+    // the side to move's legal moves with the positions they lead to, only checks when the next
+    // move has to mate, since nothing else can
+    fn children<'a>(&mut self, board: &Board<'a>, mating: bool) -> Vec<(Move, Board<'a>)> {
+        self.nodes += 1;
+
+        let moves = match mating {
+            true => board.generate_checks(board.to_move),
+            false => board.generate_moves_for_color(board.to_move),
+        };
+
+        moves
+            .iter()
+            .map(|m| {
+                let mut child = board.clone();
+                child.make_move(*m);
+                (*m, child)
+            })
+            .collect()
+    }
+
+    // whether only checks are worth trying for the attacker, which is when its next move mates
+    fn mating_move(&self, moves: u32) -> bool {
+        self.stipulation.kind == StipulationKind::Directmate && moves == 1
+    }
+
+    // whether the attacker, to move, gets there within moves against any defence
+    fn attacker_wins(&mut self, board: &Board, moves: u32) -> bool {
+        if let Some(&wins) = self.table.get(&(board.key, moves)) {
+            return wins;
+        }
+
+        let wins = board.endgame().is_none()
+            && self
+                .children(board, self.mating_move(moves))
+                .iter()
+                .any(|(_, child)| self.defence_fails(child, moves));
+
+        self.table.insert((board.key, moves), wins);
+        wins
+    }
+
+    // whether every defence to the attacker's last move loses within the moves left
+    fn defence_fails(&mut self, board: &Board, moves: u32) -> bool {
+        match self.stipulation.kind {
+            // mated is lost, any other end of the game is saved
+            StipulationKind::Directmate => match board.endgame() {
+                Some(ENDGAME::Checkmate) => true,
+                Some(_) => false,
+                None if moves == 1 => false,
+                None => self
+                    .children(board, false)
+                    .iter()
+                    .all(|(_, child)| self.attacker_wins(child, moves - 1)),
+            },
+            // the defender has to have moves, and mate with all of them or lose later
+            StipulationKind::Selfmate => {
+                board.endgame().is_none()
+                    && self.children(board, false).iter().all(|(_, child)| {
+                        child.endgame() == Some(ENDGAME::Checkmate)
+                            || (moves > 1 && self.attacker_wins(child, moves - 1))
+                    })
+            }
+            StipulationKind::Helpmate => unreachable!("helpmates have no defence"),
+        }
+    }
+
+    // the attacker's moves that get there within moves, each with the defences to it
+    fn attack_tree(&mut self, board: &Board, moves: u32) -> Vec<SolutionNode> {
+        if board.endgame().is_some() {
+            return Vec::new();
+        }
+
+        let mut tree = Vec::new();
+        for (m, child) in self.children(board, self.mating_move(moves)) {
+            if self.defence_fails(&child, moves) {
+                let children = self.defence_tree(&child, moves);
+                tree.push(SolutionNode { m, children });
+            }
+        }
+
+        tree
+    }
+
+    // every defence to a move that works, each with the attacker's moves that still work
+    fn defence_tree(&mut self, board: &Board, moves: u32) -> Vec<SolutionNode> {
+        // a mated defender has no moves to list
+        let mut tree = Vec::new();
+        for (m, child) in self.children(board, false) {
+            let children = match child.endgame() {
+                // the selfmate is done when the defender mates
+                Some(ENDGAME::Checkmate) => Vec::new(),
+                _ => self.attack_tree(&child, moves - 1),
+            };
+            tree.push(SolutionNode { m, children });
+        }
+
+        tree
+    }
+
+    // whether the side to move gets itself mated in exactly plies with the other side's help
+    fn help_works(&mut self, board: &Board, plies: u32) -> bool {
+        if let Some(&works) = self.table.get(&(board.key, plies)) {
+            return works;
+        }
+
+        let works = board.endgame().is_none()
+            && self
+                .children(board, plies == 1)
+                .iter()
+                .any(|(_, child)| match plies {
+                    1 => child.endgame() == Some(ENDGAME::Checkmate),
+                    _ => self.help_works(child, plies - 1),
+                });
+
+        self.table.insert((board.key, plies), works);
+        works
+    }
+
+    fn help_tree(&mut self, board: &Board, plies: u32) -> Vec<SolutionNode> {
+        if !self.help_works(board, plies) {
+            return Vec::new();
+        }
+
+        let mut tree = Vec::new();
+        for (m, child) in self.children(board, plies == 1) {
+            let children = match plies {
+                1 if child.endgame() == Some(ENDGAME::Checkmate) => Vec::new(),
+                1 => continue,
+                _ if !self.help_works(&child, plies - 1) => continue,
+                _ => self.help_tree(&child, plies - 1),
+            };
+            tree.push(SolutionNode { m, children });
+        }
+
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup_table::LookupTable;
+
+    fn solve(fen: &str, stipulation: &str) -> Solution {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(fen, &lookup_table);
+        ProblemSolver::new(Stipulation::parse(stipulation).unwrap()).solve(&board)
+    }
+
+    fn lines(solution: &Solution) -> Vec<Vec<String>> {
+        let mut lines: Vec<Vec<String>> = solution
+            .lines()
+            .iter()
+            .map(|line| line.iter().map(|m| m.to_uci(false)).collect())
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn sound_directmate() {
+        // the quiet key leaves the knight and the king seven defences, all met by Ra1
+        let solution = solve("k2K4/8/8/8/6n1/8/8/2R5 w - - 0 1", "#2");
+        assert!(!solution.is_cooked());
+        assert!(solution.duals().is_empty());
+
+        let lines = lines(&solution);
+        assert_eq!(lines.len(), 7);
+        assert!(lines
+            .iter()
+            .all(|line| line[0] == "d8c7" && line[2] == "c1a1"));
+    }
+
+    #[test]
+    fn cooked_directmate() {
+        let solution = solve("8/8/8/8/Q7/K1Q5/7k/8 w - - 0 1", "#2");
+        assert!(solution.is_cooked());
+
+        let keys: Vec<String> = solution
+            .tree
+            .iter()
+            .map(|node| node.m.to_uci(false))
+            .collect();
+        assert_eq!(keys, ["a4c2", "a4g4"]);
+    }
+
+    #[test]
+    fn helpmate_with_two_solutions() {
+        // the black king walks into the corner mate on f8 through e8 or through g8
+        let solution = solve("8/5k2/7R/6K1/8/8/8/8 b - - 0 1", "h#2");
+        assert!(solution.is_cooked());
+        assert!(solution.duals().is_empty());
+        assert_eq!(
+            lines(&solution),
+            [
+                ["f7e8", "g5f6", "e8f8", "h6h8"],
+                ["f7g8", "g5f6", "g8f8", "h6h8"],
+            ]
+        );
+
+        // one solution, but black can promote to a queen or a rook for the mate
+        let solution = solve("4k3/8/8/8/6r1/8/5p2/5K2 w - - 0 1", "h#2");
+        assert!(!solution.is_cooked());
+
+        let duals = solution.duals();
+        assert_eq!(duals.len(), 1);
+        assert_eq!(duals[0].line.len(), 3);
+        assert_eq!(duals[0].moves.len(), 2);
+    }
+
+    #[test]
+    fn selfmate() {
+        let solution = solve("2K5/5q2/P1k5/Q6Q/7b/8/8/8 w - - 0 1", "s#1");
+        assert!(!solution.is_cooked());
+        assert!(solution.duals().is_empty());
+        assert_eq!(lines(&solution), [["a5c7", "f7c7"]]);
+    }
+}
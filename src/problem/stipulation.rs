@@ -0,0 +1,50 @@
+use crate::problem::ProblemError;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StipulationKind {
+    // the side to move mates against any defence
+    Directmate,
+    // both sides cooperate so that the side to move is mated, moving first
+    Helpmate,
+    // the side to move forces the opponent to mate it against any defence
+    Selfmate,
+}
+
+// What a problem asks for, written #2, h#3 or s#2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stipulation {
+    pub kind: StipulationKind,
+    pub moves: u32,
+}
+
+impl Stipulation {
+    pub fn parse(s: &str) -> Result<Stipulation, ProblemError> {
+        let error = || ProblemError::InvalidStipulation(s.to_string());
+
+        let lower = s.trim().to_lowercase();
+        let (kind, moves) = match lower.split_once('#') {
+            Some(("", moves)) => (StipulationKind::Directmate, moves),
+            Some(("h", moves)) => (StipulationKind::Helpmate, moves),
+            Some(("s", moves)) => (StipulationKind::Selfmate, moves),
+            _ => return Err(error()),
+        };
+
+        match moves.parse::<u32>() {
+            Ok(moves) if moves > 0 => Ok(Stipulation { kind, moves }),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for Stipulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = match self.kind {
+            StipulationKind::Directmate => "",
+            StipulationKind::Helpmate => "h",
+            StipulationKind::Selfmate => "s",
+        };
+
+        write!(f, "{}#{}", prefix, self.moves)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::problem::ProblemError;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TwinChange {
+    // move e1 e2, the piece leaves its square for an empty one
+    Move(SQUARE, SQUARE),
+    // remove e4
+    Remove(SQUARE),
+    // add Qa1 for a white queen, qa1 for a black one
+    Add(PIECE, SQUARE),
+    // exchange a1 b2, the pieces on the two squares swap places
+    Exchange(SQUARE, SQUARE),
+}
+
+// A twin of the diagram: changes made to it, always to the diagram itself and never to the twin
+// before, that make another problem with the same stipulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Twin {
+    pub changes: Vec<TwinChange>,
+}
+
+impl Twin {
+    // changes separated by commas, e.g. "move e1 e2, remove a1, add Qa1"
+    pub fn parse(s: &str) -> Result<Twin, ProblemError> {
+        let error = || ProblemError::InvalidTwin(s.to_string());
+        let square = |token: Option<&str>| token.and_then(SQUARE::from_string).ok_or_else(error);
+
+        let mut changes = Vec::new();
+        for change in s.split(',') {
+            let mut tokens = change.split_whitespace();
+            let change = match tokens.next() {
+                Some("move") => TwinChange::Move(square(tokens.next())?, square(tokens.next())?),
+                Some("remove") => TwinChange::Remove(square(tokens.next())?),
+                Some("add") => {
+                    let token = tokens.next().ok_or_else(error)?;
+                    let mut chars = token.chars();
+                    let piece = chars.next().and_then(PIECE::from_char).ok_or_else(error)?;
+                    TwinChange::Add(piece, square(Some(chars.as_str()))?)
+                }
+                Some("exchange") => {
+                    TwinChange::Exchange(square(tokens.next())?, square(tokens.next())?)
+                }
+                _ => return Err(error()),
+            };
+
+            if tokens.next().is_some() {
+                return Err(error());
+            }
+            changes.push(change);
+        }
+
+        Ok(Twin { changes })
+    }
+
+    // the diagram with the changes made, which has to be a legal position itself
+    pub fn apply<'a>(&self, diagram: &Board<'a>) -> Result<Board<'a>, ProblemError> {
+        let illegal =
+            |errors: Vec<PositionError>| ProblemError::IllegalTwin(self.to_string(), errors);
+        let mut board = diagram.clone();
+
+        for change in &self.changes {
+            match *change {
+                TwinChange::Move(from, to) => {
+                    let piece = take(&mut board, from).ok_or_else(|| self.invalid())?;
+                    if board.piece_at_square(to).not_empty() {
+                        return Err(self.invalid());
+                    }
+                    board.add_piece(to.index(), piece);
+                }
+                TwinChange::Remove(square) => {
+                    take(&mut board, square).ok_or_else(|| self.invalid())?;
+                }
+                TwinChange::Add(piece, square) => {
+                    if board.piece_at_square(square).not_empty() {
+                        return Err(self.invalid());
+                    }
+                    board.add_piece(square.index(), piece);
+                }
+                TwinChange::Exchange(a, b) => {
+                    let piece_a = take(&mut board, a).ok_or_else(|| self.invalid())?;
+                    let piece_b = take(&mut board, b).ok_or_else(|| self.invalid())?;
+                    board.add_piece(b.index(), piece_a);
+                    board.add_piece(a.index(), piece_b);
+                }
+            }
+        }
+
+        // the en passant capture belonged to the diagram's last move, which the twin undid. The
+        // board is read back from its FEN so the key matches the new position
+        board.en_passant_target = None;
+        let board = Board::parse_fen(&board.to_fen(), board.chess960, board.lookup_table)
+            .map_err(|error| illegal(vec![error]))?;
+
+        let errors = board.validate();
+        match errors.is_empty() {
+            true => Ok(board),
+            false => Err(illegal(errors)),
+        }
+    }
+
+    fn invalid(&self) -> ProblemError {
+        ProblemError::InvalidTwin(self.to_string())
+    }
+}
+
+// Takes the piece off the square, if there is one. A king taken off can't castle any more, and
+// neither can a rook, which remove_piece sees to
+fn take(board: &mut Board, square: SQUARE) -> Option<PIECE> {
+    let piece = board.piece_at_square(square);
+    let color = piece.color()?;
+
+    board.remove_piece(square.index());
+    if piece.is_king() {
+        board.castling_rights.remove_color(color);
+    }
+
+    Some(piece)
+}
+
+impl fmt::Display for TwinChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TwinChange::Move(from, to) => write!(f, "move {} {}", from.to_fen(), to.to_fen()),
+            TwinChange::Remove(square) => write!(f, "remove {}", square.to_fen()),
+            TwinChange::Add(piece, square) => {
+                write!(f, "add {}{}", *piece as u8 as char, square.to_fen())
+            }
+            TwinChange::Exchange(a, b) => write!(f, "exchange {} {}", a.to_fen(), b.to_fen()),
+        }
+    }
+}
+
+impl fmt::Display for Twin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let changes: Vec<String> = self.changes.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", changes.join(", "))
+    }
+}
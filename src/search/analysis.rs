@@ -0,0 +1,126 @@
+use crate::board::Board;
+use crate::lookup_table::LookupTable;
+use crate::pgn::PgnGame;
+use crate::search::*;
+use std::fs::File;
+use thousands::Separable;
+
+// Searches the position to a fixed depth and prints its best lines, best first, in SAN. With
+// pgn_out they're also written there as a game, the best as the main line and the rest as
+// variations, each with its evaluation
+pub fn run_analysis(fen: &str, depth: i32, lines: usize, threads: usize, pgn_out: Option<&str>) {
+    let lookup_table = LookupTable::new();
+    let board = Board::from_fen(fen, &lookup_table);
+    let mut search = Search::new(DEFAULT_HASH_MB, SearchConfig::default());
+    search.set_threads(threads);
+
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    let start = std::time::Instant::now();
+    let results = search.analyse(&board, &[board.key], limits, lines);
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!("{}", "-".repeat(80));
+    println!("Best {} lines at depth {} for {}", lines, depth, fen);
+    println!("{}", "-".repeat(80));
+    for (i, result) in results.iter().enumerate() {
+        println!(
+            "{:>2}. {:>10}  {}",
+            i + 1,
+            uci_score(result.score),
+            board.line_to_san(&result.pv)
+        );
+    }
+
+    let nodes = results.first().map_or(0, |result| result.nodes);
+    println!("{}", "-".repeat(80));
+    println!(
+        "{} nodes in {:.2} seconds",
+        nodes.separate_with_commas(),
+        elapsed
+    );
+
+    if let Some(path) = pgn_out {
+        let pgn = analysis_pgn(&board, &results);
+        let written = File::create(path).and_then(|mut out| pgn.write_pgn(&mut out, &lookup_table));
+        if let Err(error) = written {
+            println!("can't write {}: {}", path, error);
+        }
+    }
+}
+
+// the lines of an analysis of board as a game starting there
+pub fn analysis_pgn(board: &Board, lines: &[SearchResult]) -> PgnGame {
+    let mut pgn = PgnGame::new(&board.to_fen());
+    pgn.chess960 = board.chess960;
+    pgn.set_tag("Event", "Analysis");
+
+    let root = pgn.root();
+    pgn.add_analysis(root, lines, board.lookup_table);
+    pgn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::*;
+    use crate::pgn::*;
+
+    #[test]
+    fn evals_are_from_whites_point_of_view() {
+        let eval = |score: i32, to_move: COLOR| {
+            let result = SearchResult {
+                score,
+                depth: 12,
+                ..SearchResult::default()
+            };
+            PgnEval::from_search(&result, to_move)
+        };
+
+        assert_eq!(eval(35, COLOR::WHITE).score, PgnScore::Centipawns(35));
+        assert_eq!(eval(35, COLOR::BLACK).score, PgnScore::Centipawns(-35));
+        // mating in 2 is 3 plies away
+        assert_eq!(eval(MATE - 3, COLOR::WHITE).score, PgnScore::Mate(2));
+        assert_eq!(eval(MATE - 3, COLOR::BLACK).score, PgnScore::Mate(-2));
+        assert_eq!(eval(-(MATE - 2), COLOR::BLACK).score, PgnScore::Mate(1));
+        assert_eq!(eval(35, COLOR::WHITE).depth, Some(12));
+    }
+
+    #[test]
+    fn analysis_round_trips_through_pgn() {
+        let lookup_table = LookupTable::new();
+        // black takes the queen, so white's evaluation is well below zero
+        let board = Board::from_fen("4k3/3r4/8/3Q4/8/8/8/4K3 b - - 0 1", &lookup_table);
+        let mut search = Search::default();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let lines = search.analyse(&board, &[board.key], limits, 3);
+
+        let text = analysis_pgn(&board, &lines).to_pgn(&lookup_table);
+        let game = PgnReader::new(text.as_bytes(), &lookup_table)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(game.tag("FEN"), Some("4k3/3r4/8/3Q4/8/8/8/4K3 b - - 0 1"));
+
+        // one line per root move, the best first, each with its evaluation
+        let root = &game.nodes[game.root()];
+        assert_eq!(root.children.len(), 3);
+        assert_eq!(game.mainline(), lines[0].pv);
+        for (&node, line) in root.children.iter().zip(&lines) {
+            assert_eq!(game.nodes[node].move_, line.best_move);
+            assert_eq!(
+                game.nodes[node].eval,
+                Some(PgnEval::from_search(line, COLOR::BLACK))
+            );
+        }
+
+        let best = game.nodes[root.children[0]].eval.unwrap();
+        assert!(matches!(best.score, PgnScore::Centipawns(centipawns) if centipawns < -500));
+        assert_eq!(best.depth, Some(3));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::board::Board;
+use crate::lookup_table::LookupTable;
+use crate::search::*;
+use thousands::Separable;
+
+// a spread of openings, middlegames and endgames to compare node counts on
+pub const BENCH_FENS: [&str; 10] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "2rq1rk1/pp1bppbp/2np1np1/8/3NP3/1BN1BP2/PPPQ2PP/2KR3R b - - 8 11",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1",
+];
+
+// searches every bench position to a fixed depth from a cleared search and reports the nodes it
+// took, which is what tells whether a change to move ordering or pruning helped
+pub fn run_bench(depth: i32, threads: usize, config: SearchConfig) {
+    let lookup_table = LookupTable::new();
+    let mut search = Search::new(DEFAULT_HASH_MB, config);
+    search.set_threads(threads);
+
+    let mut total_nodes = 0;
+    let start = std::time::Instant::now();
+
+    println!("{}", "-".repeat(80));
+    println!(
+        "Bench at depth {} on {} threads with {:?}",
+        depth, threads, config
+    );
+    println!("{}", "-".repeat(80));
+
+    for fen in BENCH_FENS {
+        let board = Board::from_fen(fen, &lookup_table);
+        search.clear();
+
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+        let result = search.go(&board, &[board.key], limits);
+        total_nodes += result.nodes;
+
+        println!(
+            "{:>12} nodes  {:>6}  {:>6} cp  {}",
+            result.nodes.separate_with_commas(),
+            result.best_move.to_uci(board.chess960),
+            result.score,
+            fen
+        );
+    }
+
+    let elapsed = start.elapsed().as_secs_f64();
+    println!("{}", "-".repeat(80));
+    println!(
+        "{} nodes in {:.2} seconds at {} nodes/second",
+        total_nodes.separate_with_commas(),
+        elapsed,
+        ((total_nodes as f64 / elapsed) as u64).separate_with_commas()
+    );
+}
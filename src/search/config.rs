@@ -0,0 +1,171 @@
+use crate::search::{OrderingConfig, MAX_PLY};
+use clap::ValueEnum;
+use strum_macros::Display;
+
+// the heuristics the search combines, each of which can be switched off to measure it
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Heuristic {
+    HashMove,
+    MvvLva,
+    See,
+    Killers,
+    History,
+    Countermoves,
+    ContinuationHistory,
+    NullMove,
+    Lmr,
+    Futility,
+    ReverseFutility,
+    Lmp,
+    Razoring,
+    CheckExtensions,
+    AspirationWindows,
+}
+
+// Every knob of the search in one place, so they can be tuned together. Depths are in plies,
+// margins in centipawns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    pub ordering: OrderingConfig,
+
+    // giving the opponent a free move and still failing high means this node can be cut
+    pub null_move: bool,
+    pub null_move_min_depth: i32,
+    // depth taken off the null move search, plus one per null_move_depth_divisor plies of depth
+    pub null_move_reduction: i32,
+    pub null_move_depth_divisor: i32,
+    // from this depth a null move cutoff is checked by a reduced search without null moves,
+    // which catches the zugzwangs that having pieces besides pawns doesn't rule out
+    pub null_move_verification_depth: i32,
+
+    // late quiets are searched shallower, by lmr_base + ln(depth) * ln(moves) / lmr_divisor
+    pub lmr: bool,
+    pub lmr_min_depth: i32,
+    // moves searched at full depth before any are reduced
+    pub lmr_full_depth_moves: usize,
+    pub lmr_base: f64,
+    pub lmr_divisor: f64,
+
+    // near the leaves, quiets are skipped when even futility_margin per ply can't reach alpha
+    pub futility: bool,
+    pub futility_max_depth: i32,
+    pub futility_margin: i32,
+
+    // near the leaves, a static eval reverse_futility_margin per ply above beta is cut outright
+    pub reverse_futility: bool,
+    pub reverse_futility_max_depth: i32,
+    pub reverse_futility_margin: i32,
+
+    // near the leaves, only the first lmp_base + depth² quiets are searched
+    pub lmp: bool,
+    pub lmp_max_depth: i32,
+    pub lmp_base: usize,
+
+    // near the leaves, a static eval razoring_margin per ply below alpha drops into quiescence
+    pub razoring: bool,
+    pub razoring_max_depth: i32,
+    pub razoring_margin: i32,
+
+    // positions in check are searched a ply deeper, so forcing lines aren't cut off
+    pub check_extensions: bool,
+
+    // from aspiration_min_depth, the root is first searched aspiration_window either side of the
+    // previous iteration's score, doubling the margin on every miss
+    pub aspiration_windows: bool,
+    pub aspiration_min_depth: i32,
+    pub aspiration_window: i32,
+
+    // root moves that get a line of their own, each searched with the better ones left out
+    pub multi_pv: usize,
+
+    // milliseconds kept back from every move for the time lost talking to the GUI
+    pub move_overhead: u64,
+    // the moves the clock is assumed to have to last for when the GUI doesn't say
+    pub moves_to_go: u32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            ordering: OrderingConfig::default(),
+
+            null_move: true,
+            null_move_min_depth: 3,
+            null_move_reduction: 3,
+            null_move_depth_divisor: 4,
+            null_move_verification_depth: 10,
+
+            lmr: true,
+            lmr_min_depth: 3,
+            lmr_full_depth_moves: 3,
+            lmr_base: 0.75,
+            lmr_divisor: 2.25,
+
+            futility: true,
+            futility_max_depth: 3,
+            futility_margin: 120,
+
+            reverse_futility: true,
+            reverse_futility_max_depth: 6,
+            reverse_futility_margin: 80,
+
+            lmp: true,
+            lmp_max_depth: 4,
+            lmp_base: 3,
+
+            razoring: true,
+            razoring_max_depth: 2,
+            razoring_margin: 300,
+
+            check_extensions: true,
+
+            aspiration_windows: true,
+            aspiration_min_depth: 4,
+            aspiration_window: 25,
+
+            multi_pv: 1,
+
+            move_overhead: 30,
+            moves_to_go: 30,
+        }
+    }
+}
+
+impl SearchConfig {
+    pub fn disable(&mut self, heuristic: Heuristic) {
+        match heuristic {
+            Heuristic::HashMove => self.ordering.hash_move = false,
+            Heuristic::MvvLva => self.ordering.mvv_lva = false,
+            Heuristic::See => self.ordering.see = false,
+            Heuristic::Killers => self.ordering.killers = false,
+            Heuristic::History => self.ordering.history = false,
+            Heuristic::Countermoves => self.ordering.countermoves = false,
+            Heuristic::ContinuationHistory => self.ordering.continuation_history = false,
+            Heuristic::NullMove => self.null_move = false,
+            Heuristic::Lmr => self.lmr = false,
+            Heuristic::Futility => self.futility = false,
+            Heuristic::ReverseFutility => self.reverse_futility = false,
+            Heuristic::Lmp => self.lmp = false,
+            Heuristic::Razoring => self.razoring = false,
+            Heuristic::CheckExtensions => self.check_extensions = false,
+            Heuristic::AspirationWindows => self.aspiration_windows = false,
+        }
+    }
+
+    // the late move reduction for every depth and move number, worked out once per search
+    pub fn reductions(&self) -> Vec<[i32; 64]> {
+        (0..MAX_PLY)
+            .map(|depth| {
+                let mut row = [0; 64];
+                for (moves, reduction) in row.iter_mut().enumerate().skip(1) {
+                    if depth > 0 {
+                        let r = self.lmr_base
+                            + (depth as f64).ln() * (moves as f64).ln() / self.lmr_divisor;
+                        *reduction = r.max(0.0) as i32;
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+use crate::r#move::Move;
+use crate::search::{MATE, MATE_BOUND};
+use std::fmt;
+
+// What the search reports after each iteration, formatted as a UCI info line
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: i32,
+    // which of the MultiPV lines this is, from 1 for the best
+    pub multipv: usize,
+    // the deepest ply reached, quiescence included
+    pub seldepth: usize,
+    pub score: i32,
+    pub nodes: u64,
+    pub time_ms: u128,
+    pub hashfull: usize,
+    pub pv: Vec<Move>,
+    pub chess960: bool,
+}
+
+// "cp x" for normal scores, "mate n" in moves (negative when getting mated) for mate scores
+pub fn uci_score(score: i32) -> String {
+    match score {
+        s if s >= MATE_BOUND => format!("mate {}", (MATE - s + 1) / 2),
+        s if s <= -MATE_BOUND => format!("mate -{}", (MATE + s) / 2),
+        s => format!("cp {}", s),
+    }
+}
+
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nps = self.nodes as u128 * 1000 / self.time_ms.max(1);
+        write!(
+            f,
+            "info depth {} seldepth {} multipv {} score {} nodes {} nps {} hashfull {} time {}",
+            self.depth,
+            self.seldepth,
+            self.multipv,
+            uci_score(self.score),
+            self.nodes,
+            nps,
+            self.hashfull,
+            self.time_ms
+        )?;
+
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for m in &self.pv {
+                write!(f, " {}", m.to_uci(self.chess960))?;
+            }
+        }
+
+        Ok(())
+    }
+}
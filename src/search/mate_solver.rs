@@ -0,0 +1,333 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::game::Game;
+use crate::lookup_table::LookupTable;
+use crate::r#move::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use thousands::Separable;
+
+// proof and disproof numbers saturate here, which stands for proven impossible
+const INFINITE: u32 = u32::MAX;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MateResult {
+    // the forced line, with the defence that holds out longest, ending in mate
+    Mate(Vec<Move>),
+    // no mate in the moves given by checks alone
+    NoMate,
+    // stopped or out of nodes before either was proven
+    Unknown,
+}
+
+// Proves or disproves a mate in n for the side to move with depth-first proof-number search.
+// The attacker only tries checks, so mates with a quiet move in them are not found, but the
+// trees stay narrow enough to solve deep ones. Each node keeps a proof number, how many leaves
+// still need proving for the attacker to win, and a disproof number, how many for the defender to
+// escape, and the search always expands the node that looks cheapest to settle the root.
+//
+// Numbers are kept from the side to move's point of view: phi is what it needs to settle the
+// node in its favour, delta what its opponent needs. The attacker's phi is the proof number, the
+// defender's phi the disproof number
+pub struct MateSolver {
+    // (key, plies left) to (phi, delta). Plies left always drop by one from parent to child, so
+    // the positions searched form a graph without cycles
+    table: HashMap<(u64, u32), (u32, u32)>,
+    nodes: u64,
+    max_nodes: Option<u64>,
+    deadline: Option<Instant>,
+    stop: Option<Arc<AtomicBool>>,
+    stopped: bool,
+}
+
+impl MateSolver {
+    pub fn new(max_nodes: Option<u64>) -> MateSolver {
+        MateSolver {
+            table: HashMap::new(),
+            nodes: 0,
+            max_nodes,
+            deadline: None,
+            stop: None,
+            stopped: false,
+        }
+    }
+
+    // gives up as soon as the flag is raised, as a UCI stop does
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> MateSolver {
+        self.stop = Some(stop);
+        self
+    }
+
+    // gives up once the time is up
+    pub fn with_deadline(mut self, deadline: Instant) -> MateSolver {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // looks for the shortest mate of at most the given number of moves
+    pub fn solve(&mut self, board: &Board, moves: u32) -> MateResult {
+        for n in 1..=moves {
+            let plies = 2 * n - 1;
+            if self.wins(board, plies) {
+                return MateResult::Mate(self.line(board, plies));
+            }
+            if self.stopped {
+                return MateResult::Unknown;
+            }
+        }
+
+        MateResult::NoMate
+    }
+
+    // whether the attacker mates within plies from this node, whichever side is to move
+    fn wins(&mut self, board: &Board, plies: u32) -> bool {
+        let (phi, delta) = self.mid(board, plies, INFINITE, INFINITE);
+
+        // the attacker moves when an odd number of plies is left
+        match plies % 2 == 1 {
+            true => phi == 0,
+            false => delta == 0,
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.max_nodes.is_some_and(|max| self.nodes >= max) {
+            self.stopped = true;
+        }
+        if self.nodes.is_multiple_of(1024) {
+            if let Some(stop) = &self.stop {
+                self.stopped |= stop.load(Ordering::Relaxed);
+            }
+            self.stopped |= self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+
+        self.stopped
+    }
+
+    // the numbers of a node not searched yet, or no longer in the table
+    fn numbers(&self, board: &Board, plies: u32) -> (u32, u32) {
+        self.table
+            .get(&(board.key, plies))
+            .copied()
+            .unwrap_or((1, 1))
+    }
+
+    // numbers for nodes settled without searching: the game is over, or no plies are left
+    fn terminal(&self, board: &Board, plies: u32) -> Option<(u32, u32)> {
+        let attacker_to_move = plies % 2 == 1;
+
+        match board.endgame() {
+            // whoever is mated has lost, whichever side it is
+            Some(ENDGAME::Checkmate) => Some((INFINITE, 0)),
+            // any other end is a draw, which is as good as a win for the defender
+            Some(_) if attacker_to_move => Some((INFINITE, 0)),
+            Some(_) => Some((0, INFINITE)),
+            // the defender survived every ply
+            None if plies == 0 => Some((0, INFINITE)),
+            None => None,
+        }
+    }
+
+    // checks for the attacker, every legal move for the defender (which is in check anyway)
+    fn children<'a>(&self, board: &Board<'a>, plies: u32) -> Vec<(Move, Board<'a>)> {
+        let moves = match plies % 2 == 1 {
+            true => board.generate_checks(board.to_move),
+            false => board.generate_moves_for_color(board.to_move),
+        };
+
+        moves
+            .iter()
+            .map(|m| {
+                let mut child = board.clone();
+                child.make_move(*m);
+                (*m, child)
+            })
+            .collect()
+    }
+
+    // multiple iterative deepening: searches below the node until its numbers reach either
+    // threshold, then stores and returns them
+    fn mid(&mut self, board: &Board, plies: u32, phi_limit: u32, delta_limit: u32) -> (u32, u32) {
+        self.nodes += 1;
+
+        if let Some(numbers) = self.terminal(board, plies) {
+            self.table.insert((board.key, plies), numbers);
+            return numbers;
+        }
+
+        let children = self.children(board, plies);
+        loop {
+            // 1. The node is settled in its side's favour once any child is settled against the
+            // opponent, and against it only once every child is
+            let mut phi = INFINITE;
+            let mut delta: u32 = 0;
+            let mut best = None;
+            let mut second = INFINITE;
+            for (i, (_, child)) in children.iter().enumerate() {
+                let (child_phi, child_delta) = self.numbers(child, plies - 1);
+                delta = delta.saturating_add(child_phi);
+
+                if child_delta < phi {
+                    second = phi;
+                    phi = child_delta;
+                    best = Some(i);
+                } else if child_delta < second {
+                    second = child_delta;
+                }
+            }
+
+            if phi >= phi_limit || delta >= delta_limit || self.should_stop() {
+                self.table.insert((board.key, plies), (phi, delta));
+                return (phi, delta);
+            }
+
+            // 2. Search the most promising child, until it stops being that or runs over what
+            // this node can spend on it
+            let (_, child) = &children[best.expect("a node with no children is settled")];
+            let (child_phi, _) = self.numbers(child, plies - 1);
+            let child_phi_limit = delta_limit.saturating_sub(delta).saturating_add(child_phi);
+            let child_delta_limit = phi_limit.min(second.saturating_add(1));
+
+            self.mid(child, plies - 1, child_phi_limit, child_delta_limit);
+        }
+    }
+
+    // the fewest plies within which the attacker mates from the node, if it does within plies
+    fn distance(&mut self, board: &Board, plies: u32) -> Option<u32> {
+        (plies % 2..=plies)
+            .step_by(2)
+            .find(|&plies| self.wins(board, plies))
+    }
+
+    // Walks a proven node down to the mate: the attacker picks the quickest mate, the defender
+    // the move that puts it off longest
+    fn line(&mut self, board: &Board, plies: u32) -> Vec<Move> {
+        let attacker_to_move = plies % 2 == 1;
+        if plies == 0 || board.endgame().is_some() {
+            return Vec::new();
+        }
+
+        let mut choice: Option<(Move, Board, u32)> = None;
+        for (m, child) in self.children(board, plies) {
+            let distance = match self.distance(&child, plies - 1) {
+                Some(distance) => distance,
+                None => continue,
+            };
+
+            let better = match &choice {
+                None => true,
+                Some((_, _, best)) if attacker_to_move => distance < *best,
+                Some((_, _, best)) => distance > *best,
+            };
+            if better {
+                choice = Some((m, child, distance));
+            }
+        }
+
+        match choice {
+            Some((m, child, distance)) => {
+                let mut line = vec![m];
+                line.extend(self.line(&child, distance));
+                line
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+// solves the position for a mate in at most the given moves and prints the line found, also
+// writing it to pgn_out as a game from the position
+pub fn run_mate_solver(fen: &str, moves: u32, pgn_out: Option<&str>) {
+    let lookup_table = LookupTable::new();
+    let board = Board::from_fen(fen, &lookup_table);
+    let mut solver = MateSolver::new(None);
+
+    let start = std::time::Instant::now();
+    let result = solver.solve(&board, moves);
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!("{}", "-".repeat(80));
+    println!("Mate in {} for {} in {}", moves, board.to_move, fen);
+    println!("{}", "-".repeat(80));
+    match &result {
+        MateResult::Mate(line) => println!(
+            "Mate in {}: {}",
+            line.len().div_ceil(2),
+            board.line_to_san(line)
+        ),
+        MateResult::NoMate => println!("No mate in {} by checks", moves),
+        MateResult::Unknown => println!("Gave up before finding out"),
+    }
+    println!(
+        "{} nodes in {:.2} seconds",
+        solver.nodes().separate_with_commas(),
+        elapsed
+    );
+
+    if let (MateResult::Mate(line), Some(path)) = (result, pgn_out) {
+        let mut game = Game::new(board.clone());
+        for m in line {
+            game.push(m).expect("the solver only plays legal moves");
+        }
+
+        let mut pgn = game.to_pgn();
+        pgn.set_tag(
+            "Event",
+            &format!("Mate in {}", game.moves().len().div_ceil(2)),
+        );
+        let written = File::create(path).and_then(|mut out| pgn.write_pgn(&mut out, &lookup_table));
+        if let Err(error) = written {
+            println!("can't write {}: {}", path, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(fen: &str, moves: u32, max_nodes: Option<u64>) -> (MateResult, u64) {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(fen, &lookup_table);
+        let mut solver = MateSolver::new(max_nodes);
+        (solver.solve(&board, moves), solver.nodes())
+    }
+
+    fn uci(line: &[Move]) -> Vec<String> {
+        line.iter().map(|m| m.to_uci(false)).collect()
+    }
+
+    #[test]
+    fn back_rank_mate_in_two() {
+        // the front rook gives itself up to draw the black rook off the back rank
+        let (result, _) = solve("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 2, None);
+        match result {
+            MateResult::Mate(line) => assert_eq!(uci(&line), ["d2d8", "c8d8", "d1d8"]),
+            result => panic!("no mate found: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn no_mate_within_the_node_limit() {
+        // the lone rook can't mate a king in the middle of the board in three
+        let (result, nodes) = solve("8/8/8/3k4/8/8/8/4K2R w - - 0 1", 3, Some(100_000));
+        assert_eq!(result, MateResult::NoMate);
+        assert!(nodes < 100_000);
+    }
+
+    #[test]
+    fn out_of_nodes_is_unknown() {
+        let (result, nodes) = solve("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", 2, Some(2));
+        assert_eq!(result, MateResult::Unknown);
+        assert!(nodes <= 3);
+    }
+}
@@ -0,0 +1,369 @@
+mod analysis;
+mod bench;
+mod config;
+mod info;
+mod mate_solver;
+mod ordering;
+mod skill;
+mod time_manager;
+mod transposition_table;
+mod worker;
+
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use worker::*;
+
+pub use analysis::*;
+pub use bench::*;
+pub use config::*;
+pub use info::*;
+pub use mate_solver::*;
+pub use ordering::*;
+pub use skill::*;
+pub use time_manager::*;
+pub use transposition_table::*;
+
+pub const MAX_PLY: usize = 128;
+pub const INFINITY: i32 = 32000;
+// mated at the root, a mate found n plies in scores MATE - n
+pub const MATE: i32 = 31000;
+// anything above this is a mate score
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+const DEFAULT_HASH_MB: usize = 16;
+
+// what to search for and for how long, as given by a UCI go command. Times are in milliseconds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimits {
+    pub depth: Option<i32>,
+    pub nodes: Option<u64>,
+    // stop once a mate in this many moves is found
+    pub mate: Option<i32>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    // search until stopped, whatever else was given
+    pub infinite: bool,
+    // search on the opponent's time, with the clock only starting at ponderhit
+    pub ponder: bool,
+}
+
+impl SearchLimits {
+    // the limits left for color once elapsed of its time has gone on something else
+    pub fn after(&self, color: COLOR, elapsed: Duration) -> SearchLimits {
+        let spent = elapsed.as_millis() as u64;
+        let left = |time: Option<u64>| time.map(|time| time.saturating_sub(spent));
+
+        let mut limits = *self;
+        limits.movetime = left(self.movetime);
+        match color {
+            COLOR::WHITE => limits.wtime = left(self.wtime),
+            COLOR::BLACK => limits.btime = left(self.btime),
+        }
+        limits
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    pub best_move: Move,
+    // centipawns for the side to move, or MATE minus the plies to a mate
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: u64,
+    // the expected line of play, starting with best_move
+    pub pv: Vec<Move>,
+}
+
+// Iterative deepening principal variation search with a quiescence search at the leaves, run
+// on as many threads as asked for (lazy SMP). It keeps its transposition table and move ordering
+// tables between calls to go, so a game should use one Search throughout and clear it for the
+// next game
+pub struct Search {
+    pub config: SearchConfig,
+    // print a UCI info line after every iteration
+    pub print_info: bool,
+    // how well to play, full strength unless limited
+    pub skill: Skill,
+    // set from outside, by another thread, to end the search as soon as possible
+    pub stop: Arc<AtomicBool>,
+    // raised while a ponder search waits for ponderhit, lowering it starts the clock
+    pub ponder: Arc<AtomicBool>,
+
+    hash_mb: usize,
+    shared: Arc<SharedState>,
+    workers: Vec<Worker>,
+    // picks the moves of a limited skill
+    rng: StdRng,
+}
+
+impl Search {
+    pub fn new(hash_mb: usize, config: SearchConfig) -> Search {
+        let mut search = Search {
+            config,
+            print_info: false,
+            skill: Skill::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            ponder: Arc::new(AtomicBool::new(false)),
+
+            hash_mb,
+            shared: Arc::new(SharedState::new(hash_mb, 1)),
+            workers: Vec::new(),
+            rng: StdRng::from_entropy(),
+        };
+        search.set_threads(1);
+        search
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    // starts over with a new hash table and workers for the new thread count
+    pub fn set_threads(&mut self, threads: usize) {
+        let threads = threads.max(1);
+        self.shared = Arc::new(SharedState::new(self.hash_mb, threads));
+        self.workers = (0..threads)
+            .map(|id| {
+                let shared = self.shared.clone();
+                Worker::new(
+                    id,
+                    self.config,
+                    shared,
+                    self.stop.clone(),
+                    self.ponder.clone(),
+                )
+            })
+            .collect();
+    }
+
+    // resizes the hash table, which empties it
+    pub fn set_hash(&mut self, hash_mb: usize) {
+        self.hash_mb = hash_mb.max(1);
+        self.set_threads(self.threads());
+    }
+
+    pub fn hashfull(&self) -> usize {
+        self.shared.tt.hashfull()
+    }
+
+    // forget everything learned, for a new game
+    pub fn clear(&mut self) {
+        self.shared.tt.clear();
+        for worker in &mut self.workers {
+            worker.ordering.clear();
+        }
+    }
+
+    // searches the position deeper and deeper until a limit is hit. history holds the keys of the
+    // positions of the game so far, ending with this one, so repetitions can be seen as draws
+    pub fn go(&mut self, board: &Board, history: &[u64], limits: SearchLimits) -> SearchResult {
+        // 1. A limited skill searches less, and keeps a few lines to choose from
+        let mut limits = limits;
+        let mut multi_pv = self.config.multi_pv;
+        if self.skill.is_limited() {
+            limits.depth = Some(
+                limits
+                    .depth
+                    .map_or(self.skill.depth(), |depth| depth.min(self.skill.depth())),
+            );
+            limits.nodes = Some(
+                limits
+                    .nodes
+                    .map_or(self.skill.nodes(), |nodes| nodes.min(self.skill.nodes())),
+            );
+            multi_pv = multi_pv.max(self.skill.multi_pv());
+        }
+
+        // 2. and then doesn't always play the best of them
+        let mut lines = self.run(board, history, limits, multi_pv, self.config.multi_pv);
+        let chosen = match self.skill.is_limited() {
+            true => self.skill.pick(&lines, &mut self.rng),
+            false => 0,
+        };
+        let mut result = lines.swap_remove(chosen);
+
+        // stopped before even one move was searched, any legal move beats none
+        if result.best_move.is_null() {
+            result.best_move = board
+                .generate_moves_for_color(board.to_move)
+                .iter()
+                .next()
+                .copied()
+                .unwrap_or(Move::NULL);
+        }
+
+        result
+    }
+
+    // The best lines from the position, best first, each with its own score and principal
+    // variation, for analysis. Fewer come back when there are fewer legal moves, or when the
+    // search was stopped before it finished the first iteration. Always at full strength
+    pub fn analyse(
+        &mut self,
+        board: &Board,
+        history: &[u64],
+        limits: SearchLimits,
+        lines: usize,
+    ) -> Vec<SearchResult> {
+        self.run(board, history, limits, lines, lines)
+            .into_iter()
+            .filter(|line| !line.best_move.is_null())
+            .collect()
+    }
+
+    // Runs every thread on the position for multi_pv lines and returns the lines of the one whose
+    // move wins, with the nodes of all of them. There's always at least one line, if maybe
+    // without a move. Only the first shown lines get info printed, as a limited skill searches
+    // more lines than were asked for
+    fn run(
+        &mut self,
+        board: &Board,
+        history: &[u64],
+        limits: SearchLimits,
+        multi_pv: usize,
+        shown: usize,
+    ) -> Vec<SearchResult> {
+        let config = SearchConfig {
+            multi_pv,
+            ..self.config
+        };
+
+        self.shared.abort.store(false, Ordering::Relaxed);
+        for nodes in &self.shared.nodes {
+            nodes.store(0, Ordering::Relaxed);
+        }
+        let print_lines = match self.print_info {
+            true => shown,
+            false => 0,
+        };
+        for worker in &mut self.workers {
+            worker.start(board, history, limits, config, print_lines);
+        }
+
+        // the helpers run until the main thread is done and raises the abort flag
+        let shared = self.shared.clone();
+        let (main, helpers) = self.workers.split_first_mut().expect("no search threads");
+        std::thread::scope(|scope| {
+            for helper in helpers {
+                let board = board.clone();
+                scope.spawn(move || helper.iterate(&board));
+            }
+
+            main.iterate(board);
+            shared.abort.store(true, Ordering::Relaxed);
+        });
+
+        // the thread whose move won the vote reports, unless the main thread already did
+        let chosen = self.vote();
+        let worker = &self.workers[chosen];
+        if chosen != 0 {
+            for line in 0..worker.lines.len().min(print_lines) {
+                println!("{}", worker.info(board, line));
+            }
+        }
+
+        let mut lines = match worker.lines.is_empty() {
+            true => vec![worker.result.clone()],
+            false => worker.lines.clone(),
+        };
+        for line in &mut lines {
+            line.nodes = self.shared.total_nodes();
+        }
+
+        lines
+    }
+
+    // Picks the thread to take the move from. Every thread votes for its best move by how deep it
+    // got and how well the move scored, and the deepest thread behind the winning move reports.
+    // A proven mate beats any vote
+    fn vote(&self) -> usize {
+        let finished: Vec<&Worker> = self
+            .workers
+            .iter()
+            .filter(|worker| worker.result.depth > 0)
+            .collect();
+        let lowest = match finished.iter().map(|worker| worker.result.score).min() {
+            Some(lowest) => lowest,
+            None => return 0,
+        };
+
+        let votes = |m: Move| -> i64 {
+            finished
+                .iter()
+                .filter(|worker| worker.result.best_move == m)
+                .map(|worker| {
+                    (worker.result.score - lowest + 14) as i64 * worker.result.depth as i64
+                })
+                .sum()
+        };
+
+        let mut best = &self.workers[0];
+        for worker in &finished {
+            let (result, best_result) = (&worker.result, &best.result);
+
+            let better = match (result.score >= MATE_BOUND, best_result.score >= MATE_BOUND) {
+                (true, _) => result.score > best_result.score,
+                (false, true) => false,
+                (false, false) => {
+                    let (mine, theirs) = (votes(result.best_move), votes(best_result.best_move));
+                    mine > theirs || (mine == theirs && result.depth > best_result.depth)
+                }
+            };
+            if better {
+                best = worker;
+            }
+        }
+
+        best.id
+    }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Search::new(DEFAULT_HASH_MB, SearchConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup_table::LookupTable;
+
+    #[test]
+    fn analyse_ranks_distinct_root_moves() {
+        let lookup_table = LookupTable::new();
+        // the rook takes the loose queen, anything else leaves a queen down
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", &lookup_table);
+        let mut search = Search::default();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+
+        let lines = search.analyse(&board, &[board.key], limits, 3);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].best_move, board.parse_uci_move("d2d5").unwrap());
+        assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        assert_ne!(lines[0].best_move, lines[1].best_move);
+        assert_ne!(lines[0].best_move, lines[2].best_move);
+        assert_ne!(lines[1].best_move, lines[2].best_move);
+        assert!(lines
+            .iter()
+            .all(|line| line.pv.first() == Some(&line.best_move)));
+
+        // a king with one move has only one line to give
+        let board = Board::from_fen("k7/8/8/8/8/8/8/KR6 b - - 0 1", &lookup_table);
+        assert_eq!(search.analyse(&board, &[board.key], limits, 3).len(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+use crate::search::MAX_PLY;
+
+// history scores saturate towards this, so old cutoffs fade out as new ones come in (gravity)
+const MAX_HISTORY: i32 = 16384;
+
+// which move ordering heuristics the move picker uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderingConfig {
+    // the move stored for the position in the transposition table goes first
+    pub hash_move: bool,
+    // captures sorted by most valuable victim, then least valuable attacker
+    pub mvv_lva: bool,
+    // captures that lose material by static exchange go after the quiets
+    pub see: bool,
+    // two quiet moves per ply that caused a cutoff in a sibling node
+    pub killers: bool,
+    // quiets sorted by how often they caused cutoffs, by side, source and target
+    pub history: bool,
+    // the quiet that last refuted the opponent's previous move
+    pub countermoves: bool,
+    // history of quiets following the previous two moves, by piece and target
+    pub continuation_history: bool,
+}
+
+impl Default for OrderingConfig {
+    fn default() -> Self {
+        OrderingConfig {
+            hash_move: true,
+            mvv_lva: true,
+            see: true,
+            killers: true,
+            history: true,
+            countermoves: true,
+            continuation_history: true,
+        }
+    }
+}
+
+// what countermoves and continuation history need to know about a move already played: the
+// piece that moved and where it went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PieceTo {
+    pub piece: PIECE,
+    pub to: SQUARE,
+}
+
+impl PieceTo {
+    pub fn of(board: &Board, m: Move) -> PieceTo {
+        PieceTo {
+            piece: board.piece_at_square(m.source()),
+            to: m.target(),
+        }
+    }
+
+    fn index(&self) -> usize {
+        self.piece.index() * 64 + self.to.index()
+    }
+}
+
+// most valuable victim, least valuable attacker, with promotions counted as winning material
+pub fn mvv_lva(board: &Board, m: Move) -> i32 {
+    let attacker = board.piece_at_square(m.source()).piece_type();
+    let victim = match m.is_en_passant() {
+        true => PieceType::PAWN,
+        false => board.piece_at_square(m.target()).piece_type(),
+    };
+    let promotion = m.promotion().map_or(0, |p| p.value());
+
+    victim.value() * 10 - attacker.value() + promotion
+}
+
+// Everything the search learns about which moves work, kept between searches so the next move
+// of the game starts with a good ordering. The move picker reads it, the search updates it
+// after every cutoff
+pub struct MoveOrdering {
+    pub config: OrderingConfig,
+
+    killers: [[Move; 2]; MAX_PLY],
+    // [color][source][target]
+    history: Vec<i32>,
+    // [piece][target] of the previous move
+    countermoves: Vec<Move>,
+    // [piece][target] of an earlier move, then [piece][target] of this one
+    continuation_history: Vec<i32>,
+}
+
+impl MoveOrdering {
+    pub fn new(config: OrderingConfig) -> MoveOrdering {
+        MoveOrdering {
+            config,
+            killers: [[Move::NULL; 2]; MAX_PLY],
+            history: vec![0; 2 * 64 * 64],
+            countermoves: vec![Move::NULL; 12 * 64],
+            continuation_history: vec![0; 12 * 64 * 12 * 64],
+        }
+    }
+
+    // forget everything, for a new game
+    pub fn clear(&mut self) {
+        *self = MoveOrdering::new(self.config);
+    }
+
+    // killers only make sense for the positions of one search, the rest carries over
+    pub fn clear_killers(&mut self) {
+        self.killers = [[Move::NULL; 2]; MAX_PLY];
+    }
+
+    pub fn killers(&self, ply: usize) -> [Move; 2] {
+        match self.config.killers {
+            true => self.killers[ply],
+            false => [Move::NULL; 2],
+        }
+    }
+
+    // the quiet that refuted the opponent's previous move the last time it was played
+    pub fn countermove(&self, previous: Option<PieceTo>) -> Move {
+        match (self.config.countermoves, previous) {
+            (true, Some(previous)) => self.countermoves[previous.index()],
+            _ => Move::NULL,
+        }
+    }
+
+    // how promising a quiet move looks from the cutoffs seen so far
+    pub fn quiet_score(&self, board: &Board, m: Move, previous: &[Option<PieceTo>; 2]) -> i32 {
+        let mut score = 0;
+
+        if self.config.history {
+            score += self.history[Self::history_index(board.to_move, m)];
+        }
+
+        if self.config.continuation_history {
+            let current = PieceTo::of(board, m);
+            for earlier in previous.iter().flatten() {
+                score += self.continuation_history[Self::continuation_index(*earlier, current)];
+            }
+        }
+
+        score
+    }
+
+    // whether quiets should be sorted at all, or are as good as in generation order
+    pub fn scores_quiets(&self) -> bool {
+        self.config.history || self.config.continuation_history
+    }
+
+    // rewards the quiet move that caused a beta cutoff and punishes the quiets searched before it
+    // that didn't, all scaled by depth since cutoffs deep in the tree say more
+    pub fn update_quiets(
+        &mut self,
+        board: &Board,
+        best: Move,
+        tried: &[Move],
+        depth: i32,
+        ply: usize,
+        previous: &[Option<PieceTo>; 2],
+    ) {
+        // 1. Killers, keeping two different ones
+        if self.config.killers && self.killers[ply][0] != best {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = best;
+        }
+
+        // 2. Countermove to the opponent's previous move
+        if let (true, Some(last)) = (self.config.countermoves, previous[0]) {
+            self.countermoves[last.index()] = best;
+        }
+
+        let bonus = (depth * depth).min(400) * 16;
+        for m in tried.iter().copied().chain(std::iter::once(best)) {
+            let bonus = match m == best {
+                true => bonus,
+                false => -bonus,
+            };
+
+            // 3. Butterfly history
+            if self.config.history {
+                Self::apply_gravity(
+                    &mut self.history[Self::history_index(board.to_move, m)],
+                    bonus,
+                );
+            }
+
+            // 4. Continuation history
+            if self.config.continuation_history {
+                let current = PieceTo::of(board, m);
+                for earlier in previous.iter().flatten() {
+                    let index = Self::continuation_index(*earlier, current);
+                    Self::apply_gravity(&mut self.continuation_history[index], bonus);
+                }
+            }
+        }
+    }
+
+    // moves the entry by bonus, less the closer it already is to MAX_HISTORY in that direction
+    fn apply_gravity(entry: &mut i32, bonus: i32) {
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    fn history_index(color: COLOR, m: Move) -> usize {
+        (color.index() * 64 + m.source().index()) * 64 + m.target().index()
+    }
+
+    fn continuation_index(earlier: PieceTo, current: PieceTo) -> usize {
+        earlier.index() * 12 * 64 + current.index()
+    }
+}
@@ -0,0 +1,159 @@
+use crate::search::{SearchResult, MATE_BOUND};
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+
+// The levels only rank themselves against each other. They have never been played against rated
+// opponents, so no Elo is claimed for any of them and UCI_Elo isn't offered
+pub const MAX_SKILL_LEVEL: u32 = 20;
+
+// lines the search keeps to choose from when playing below full strength
+const SKILL_MULTI_PV: usize = 4;
+// the error model's temperature in centipawns at level 0, halving every TEMPERATURE_HALVING levels
+const BASE_TEMPERATURE: f64 = 200.0;
+const TEMPERATURE_HALVING: f64 = 4.0;
+// moves losing more than this many temperatures against the best are never played
+const MAX_LOSS: f64 = 3.0;
+
+// How well to play, from level 0 to full strength at MAX_SKILL_LEVEL. A weaker level searches
+// shallower and fewer nodes, then picks among the best few root moves at random, weighted by how
+// much each loses against the best: a move costing a temperature is e times less likely than the
+// best, so small slips are common and blunders rare, as with people
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Skill {
+    level: u32,
+}
+
+impl Skill {
+    pub fn from_level(level: u32) -> Skill {
+        Skill {
+            level: level.min(MAX_SKILL_LEVEL),
+        }
+    }
+
+    // whether the engine holds back at all
+    pub fn is_limited(&self) -> bool {
+        self.level < MAX_SKILL_LEVEL
+    }
+
+    // one ply per level, from a single ply at level 0
+    pub fn depth(&self) -> i32 {
+        1 + self.level as i32
+    }
+
+    // from a few hundred nodes at level 0, half as many again every level
+    pub fn nodes(&self) -> u64 {
+        (400.0 * 1.5f64.powi(self.level as i32)) as u64
+    }
+
+    pub fn multi_pv(&self) -> usize {
+        SKILL_MULTI_PV
+    }
+
+    fn temperature(&self) -> f64 {
+        BASE_TEMPERATURE * 0.5f64.powf(self.level as f64 / TEMPERATURE_HALVING)
+    }
+
+    // the index of the line to play out of lines ranked best first, always the best at full
+    // strength
+    pub fn pick(&self, lines: &[SearchResult], rng: &mut impl Rng) -> usize {
+        let best = match lines.first() {
+            Some(best) if self.is_limited() => best.score,
+            _ => return 0,
+        };
+
+        // a mate found is always played, and so is anything when getting mated, where every
+        // move loses anyway
+        if best.abs() >= MATE_BOUND {
+            return 0;
+        }
+
+        let temperature = self.temperature();
+        let weights: Vec<f64> = lines
+            .iter()
+            .map(|line| {
+                let loss = (best - line.score) as f64 / temperature;
+                match line.best_move.is_null() || loss > MAX_LOSS {
+                    true => 0.0,
+                    false => (-loss).exp(),
+                }
+            })
+            .collect();
+
+        match WeightedIndex::new(&weights) {
+            Ok(distribution) => distribution.sample(rng),
+            Err(_) => 0,
+        }
+    }
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Skill::from_level(MAX_SKILL_LEVEL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::*;
+    use crate::r#move::Move;
+    use crate::search::MATE;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // lines ranked best first, all starting with the same move as only the scores matter
+    fn lines(scores: &[i32]) -> Vec<SearchResult> {
+        let m = Move::new(SQUARE::E2, SQUARE::E4, MoveFlag::DoublePawnPush);
+        scores
+            .iter()
+            .map(|&score| SearchResult {
+                best_move: m,
+                score,
+                depth: 1,
+                nodes: 0,
+                pv: vec![m],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn levels_get_stronger() {
+        for level in 0..MAX_SKILL_LEVEL {
+            let (skill, next) = (Skill::from_level(level), Skill::from_level(level + 1));
+            assert!(skill.is_limited());
+            assert!(skill.depth() < next.depth());
+            assert!(skill.nodes() < next.nodes());
+            assert!(skill.temperature() > next.temperature());
+        }
+
+        assert!(!Skill::from_level(MAX_SKILL_LEVEL).is_limited());
+        assert_eq!(Skill::from_level(100), Skill::default());
+    }
+
+    #[test]
+    fn full_strength_plays_the_best_line() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lines = lines(&[30, 29, 28, 27]);
+        assert!((0..1000).all(|_| Skill::default().pick(&lines, &mut rng) == 0));
+    }
+
+    #[test]
+    fn limited_skill_slips_but_never_blunders() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let skill = Skill::from_level(0);
+
+        // the last line loses more than MAX_LOSS temperatures
+        let lines = lines(&[100, 90, 0, -700]);
+        let mut picked = [0; 4];
+        for _ in 0..1000 {
+            picked[skill.pick(&lines, &mut rng)] += 1;
+        }
+        assert_eq!(picked[3], 0);
+        assert!(picked[0] > picked[1] && picked[1] > picked[2] && picked[2] > 0);
+
+        // a mate found is never given up
+        let mate = self::lines(&[MATE - 3, 50]);
+        assert!((0..1000).all(|_| skill.pick(&mate, &mut rng) == 0));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::enums::*;
+use crate::r#move::Move;
+use crate::search::{SearchConfig, SearchLimits};
+use std::time::{Duration, Instant};
+
+// the soft limit can stretch to this many times its base when the search is unsettled, and the
+// hard limit never goes past this many times the base either
+const MAX_SCALE: f64 = 2.5;
+const HARD_LIMIT_FACTOR: f64 = 4.0;
+// never plan to use more than this share of the clock on one move
+const MAX_CLOCK_SHARE: f64 = 0.8;
+// a score falling by this many centipawns between iterations counts as a full drop
+const SCORE_DROP: i32 = 60;
+
+// Decides how long to think about a move. The soft limit is checked between iterations and
+// scaled by how settled the search looks: a best move that keeps changing or a score that drops
+// earn more time, a stable one less. The hard limit is checked during the search and is never
+// overrun, whatever the soft limit says. While pondering the clock isn't running, it only starts
+// at ponderhit
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    start: Instant,
+    // when the clock started, None while pondering
+    clock: Option<Instant>,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+
+    best_move: Move,
+    // how often the best move changed lately, halved every iteration
+    best_move_changes: f64,
+    score: Option<i32>,
+    scale: f64,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, color: COLOR, config: &SearchConfig) -> TimeManager {
+        let (soft, hard) = Self::limits(limits, color, config);
+        let start = Instant::now();
+
+        TimeManager {
+            start,
+            clock: match limits.ponder {
+                true => None,
+                false => Some(start),
+            },
+            soft,
+            hard,
+            best_move: Move::NULL,
+            best_move_changes: 0.0,
+            score: None,
+            scale: 1.0,
+        }
+    }
+
+    // the soft and hard limits for the go command, if it gave any time to keep to
+    fn limits(
+        limits: &SearchLimits,
+        color: COLOR,
+        config: &SearchConfig,
+    ) -> (Option<Duration>, Option<Duration>) {
+        if limits.infinite {
+            return (None, None);
+        }
+
+        // 1. A fixed time per move is used in full
+        if let Some(movetime) = limits.movetime {
+            let time = Duration::from_millis(movetime.saturating_sub(config.move_overhead).max(1));
+            return (Some(time), Some(time));
+        }
+
+        // 2. Otherwise the clock is split between the moves left to the next time control, with
+        // most of the increment spent on top
+        let (time, increment) = match color {
+            COLOR::WHITE => (limits.wtime, limits.winc.unwrap_or(0)),
+            COLOR::BLACK => (limits.btime, limits.binc.unwrap_or(0)),
+        };
+        let time = match time {
+            Some(time) => time.saturating_sub(config.move_overhead).max(1) as f64,
+            None => return (None, None),
+        };
+        let moves_to_go = limits
+            .movestogo
+            .unwrap_or(config.moves_to_go)
+            .clamp(1, config.moves_to_go) as f64;
+
+        let max_time = time * MAX_CLOCK_SHARE;
+        let soft = (time / moves_to_go + increment as f64 * 0.75).min(max_time);
+        let hard = (soft * HARD_LIMIT_FACTOR).min(max_time);
+
+        (
+            Some(Duration::from_secs_f64(soft / 1000.0)),
+            Some(Duration::from_secs_f64(hard / 1000.0)),
+        )
+    }
+
+    // whether there is a clock to keep to at all, which there isn't yet while pondering
+    pub fn is_timed(&self) -> bool {
+        self.hard.is_some() && self.clock.is_some()
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.clock.is_none()
+    }
+
+    // the opponent played the move pondered on, so the search is now on the clock
+    pub fn ponderhit(&mut self) {
+        self.clock.get_or_insert_with(Instant::now);
+    }
+
+    // time since the search started, pondering included
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn limit_reached(&self, limit: Option<Duration>) -> bool {
+        match (limit, self.clock) {
+            (Some(limit), Some(clock)) => clock.elapsed() >= limit,
+            _ => false,
+        }
+    }
+
+    // when the hard limit runs out, if the clock is running and there is one
+    pub fn deadline(&self) -> Option<Instant> {
+        Some(self.clock? + self.hard?)
+    }
+
+    pub fn hard_limit_reached(&self) -> bool {
+        self.limit_reached(self.hard)
+    }
+
+    // takes in the result of an iteration, to judge how settled the search is
+    pub fn update(&mut self, best_move: Move, score: i32) {
+        self.best_move_changes /= 2.0;
+        if !self.best_move.is_null() && best_move != self.best_move {
+            self.best_move_changes += 1.0;
+        }
+        self.best_move = best_move;
+
+        // 1. Instability: each recent change of mind is worth up to another base of time
+        let instability = 1.0 + self.best_move_changes;
+
+        // 2. A falling score means trouble was found, which is worth a longer look
+        let drop = match self.score {
+            Some(previous) => (previous - score).clamp(0, SCORE_DROP) as f64 / SCORE_DROP as f64,
+            None => 0.0,
+        };
+        self.score = Some(score);
+
+        // 3. A best move that held all along needs less time than the base
+        let stability = match self.best_move_changes < 0.1 {
+            true => 0.7,
+            false => 1.0,
+        };
+
+        self.scale = (instability * (1.0 + drop) * stability).min(MAX_SCALE);
+    }
+
+    // whether another iteration is worth starting
+    pub fn soft_limit_reached(&self) -> bool {
+        self.limit_reached(self.soft.map(|soft| soft.mul_f64(self.scale)))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::enums::*;
+use crate::r#move::*;
+use crate::search::MATE_BOUND;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TTEntry {
+    pub key: u64,
+    pub best_move: Move,
+    pub score: i32,
+    pub depth: i32,
+    pub bound: Bound,
+}
+
+impl TTEntry {
+    // move in bits 0-15, score in 16-31, depth in 32-47 and bound in 48-49. Bounds count from 1,
+    // so an empty slot is all zeros
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+
+        self.best_move.bits() as u64
+            | (self.score as i16 as u16 as u64) << 16
+            | (self.depth as i16 as u16 as u64) << 32
+            | bound << 48
+    }
+
+    fn unpack(key: u64, data: u64) -> Option<TTEntry> {
+        let bound = match data >> 48 & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+
+        Some(TTEntry {
+            key,
+            best_move: Move::from_bits(data as u16)?,
+            score: (data >> 16) as u16 as i16 as i32,
+            depth: (data >> 32) as u16 as i16 as i32,
+            bound,
+        })
+    }
+}
+
+// The key is stored xored with the data, so a slot torn by two threads writing it at once no
+// longer matches its key and reads as a miss instead of as another position's entry
+#[derive(Debug, Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+// Scores and best moves of positions already searched, indexed by zobrist key. One entry per
+// slot, replaced when the new search went at least as deep or is for a different position.
+// Shared by every search thread without locks
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let count = (size_mb * 1024 * 1024 / std::mem::size_of::<Slot>()).max(1);
+
+        TranspositionTable {
+            slots: (0..count).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        // the high half of a 128 bit product spreads keys evenly over any table size
+        ((key as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+
+    fn read(&self, index: usize) -> Option<TTEntry> {
+        let slot = &self.slots[index];
+        let data = slot.data.load(Ordering::Relaxed);
+        let key = slot.key.load(Ordering::Relaxed) ^ data;
+
+        TTEntry::unpack(key, data)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        self.read(self.index(key)).filter(|entry| entry.key == key)
+    }
+
+    pub fn store(&self, key: u64, best_move: Move, score: i32, depth: i32, bound: Bound) {
+        let index = self.index(key);
+        let old = self.read(index).filter(|old| old.key == key);
+
+        if let Some(old) = old {
+            if old.depth > depth && bound != Bound::Exact {
+                return;
+            }
+        }
+
+        // a new search of the same position that found no best move keeps the old one
+        let best_move = match (old, best_move.is_null()) {
+            (Some(old), true) => old.best_move,
+            _ => best_move,
+        };
+
+        let entry = TTEntry {
+            key,
+            best_move,
+            score,
+            depth,
+            bound,
+        };
+        let data = entry.pack();
+
+        let slot = &self.slots[index];
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    // permille of the table in use, as UCI reports it
+    pub fn hashfull(&self) -> usize {
+        let sample = self.slots.len().min(1000);
+        self.slots[..sample]
+            .iter()
+            .filter(|slot| slot.data.load(Ordering::Relaxed) != 0)
+            .count()
+            * 1000
+            / sample
+    }
+}
+
+// Mate scores count plies from the root, but an entry may be read at a different ply than it was
+// written at, so they are stored counting from the node itself instead
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        s if s >= MATE_BOUND => s + ply as i32,
+        s if s <= -MATE_BOUND => s - ply as i32,
+        s => s,
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        s if s >= MATE_BOUND => s - ply as i32,
+        s if s <= -MATE_BOUND => s + ply as i32,
+        s => s,
+    }
+}
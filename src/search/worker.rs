@@ -0,0 +1,759 @@
+use crate::board::Board;
+use crate::enums::*;
+use crate::r#move::*;
+use crate::search::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+// What the threads of a search share: the hash table, a flag the main thread raises to end the
+// helpers once it is done, and every thread's node count
+pub(crate) struct SharedState {
+    pub tt: TranspositionTable,
+    pub abort: AtomicBool,
+    pub nodes: Vec<AtomicU64>,
+}
+
+impl SharedState {
+    pub fn new(hash_mb: usize, threads: usize) -> SharedState {
+        SharedState {
+            tt: TranspositionTable::new(hash_mb),
+            abort: AtomicBool::new(false),
+            nodes: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub fn total_nodes(&self) -> u64 {
+        self.nodes.iter().map(|n| n.load(Ordering::Relaxed)).sum()
+    }
+}
+
+// One thread of the search, with everything it doesn't share with the others. Worker 0 is the
+// main thread: it keeps to the limits and reports, while the helpers search the same root with
+// varied depths until it tells them to stop, filling the shared hash table as they go
+pub(crate) struct Worker {
+    pub id: usize,
+    pub ordering: MoveOrdering,
+    // the last iteration this worker finished: its best line, and every MultiPV line best first
+    pub result: SearchResult,
+    pub lines: Vec<SearchResult>,
+    pub seldepth: usize,
+
+    config: SearchConfig,
+    shared: Arc<SharedState>,
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+    // how many of the best lines to print an info line for after every iteration
+    print_lines: usize,
+    // [depth][moves searched] late move reductions, from the config
+    reductions: Vec<[i32; 64]>,
+
+    limits: SearchLimits,
+    pub time: TimeManager,
+    nodes: u64,
+    stopped: bool,
+
+    // triangular PV table: pv[ply][ply..pv_length[ply]] is the best line found from ply on
+    pv: Vec<[Move; MAX_PLY]>,
+    pv_length: [usize; MAX_PLY],
+
+    // keys of every position from the start of the game down to the current node
+    keys: Vec<u64>,
+    // the move made at each ply of the current line, for countermoves and continuation history.
+    // None for a null move
+    stack: [Option<PieceTo>; MAX_PLY],
+    // null moves are off below this ply while a null move cutoff is being verified
+    null_move_min_ply: usize,
+    // root moves left out of the search, as they already have a MultiPV line
+    excluded: Vec<Move>,
+}
+
+impl Worker {
+    pub fn new(
+        id: usize,
+        config: SearchConfig,
+        shared: Arc<SharedState>,
+        stop: Arc<AtomicBool>,
+        ponder: Arc<AtomicBool>,
+    ) -> Worker {
+        Worker {
+            id,
+            ordering: MoveOrdering::new(config.ordering),
+            result: SearchResult::default(),
+            lines: Vec::new(),
+            seldepth: 0,
+
+            config,
+            shared,
+            stop,
+            ponder,
+            print_lines: 0,
+            reductions: config.reductions(),
+
+            limits: SearchLimits::default(),
+            time: TimeManager::new(&SearchLimits::default(), COLOR::WHITE, &config),
+            nodes: 0,
+            stopped: false,
+
+            pv: vec![[Move::NULL; MAX_PLY]; MAX_PLY],
+            pv_length: [0; MAX_PLY],
+
+            keys: Vec::new(),
+            stack: [None; MAX_PLY],
+            null_move_min_ply: 0,
+            excluded: Vec::new(),
+        }
+    }
+
+    // gets ready for a new search, taking on any change to the config since the last one
+    pub fn start(
+        &mut self,
+        board: &Board,
+        history: &[u64],
+        limits: SearchLimits,
+        config: SearchConfig,
+        print_lines: usize,
+    ) {
+        if config != self.config {
+            self.reductions = config.reductions();
+            self.ordering.config = config.ordering;
+            self.config = config;
+        }
+
+        self.print_lines = match self.id {
+            0 => print_lines,
+            _ => 0,
+        };
+        self.time = TimeManager::new(&limits, board.to_move, &self.config);
+        self.limits = limits;
+        self.result = SearchResult::default();
+        self.lines.clear();
+        self.nodes = 0;
+        self.seldepth = 0;
+        self.stopped = false;
+        self.pv_length = [0; MAX_PLY];
+        self.keys = history.to_vec();
+        if self.keys.last() != Some(&board.key) {
+            self.keys.push(board.key);
+        }
+        self.null_move_min_ply = 0;
+        self.ordering.clear_killers();
+    }
+
+    // iterative deepening until a limit is hit, or for a helper until the main thread is done
+    pub fn iterate(&mut self, board: &Board) {
+        // with a single legal move there is nothing to think about, beyond having a score to show
+        let root_moves = board.generate_moves_for_color(board.to_move).len();
+        let single_reply = root_moves == 1;
+        let multi_pv = self.config.multi_pv.clamp(1, root_moves.max(1));
+
+        // half the helpers search a ply deeper than the iteration they're on, so the threads
+        // don't all finish the same depths in the same order
+        let offset = (self.id % 2) as i32;
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY as i32 - 1);
+
+        for iteration in 1..=max_depth {
+            let depth = (iteration + offset).min(max_depth);
+
+            // 1. One search per line, each leaving out the root moves of the lines before it
+            let mut lines: Vec<SearchResult> = Vec::new();
+            self.excluded.clear();
+            while lines.len() < multi_pv {
+                let previous = match self.lines.get(lines.len()) {
+                    Some(line) => line.score,
+                    None => self.result.score,
+                };
+                let score = self.aspiration(board, depth, previous);
+
+                // an unfinished search can't be trusted, unless there's nothing else yet
+                if self.stopped && (self.result.depth > 0 || !lines.is_empty()) {
+                    break;
+                }
+
+                let pv = self.principal_variation(board);
+                let best_move = pv.first().copied().unwrap_or(Move::NULL);
+                lines.push(SearchResult {
+                    best_move,
+                    score,
+                    depth,
+                    nodes: self.nodes,
+                    pv,
+                });
+
+                if self.stopped || best_move.is_null() {
+                    break;
+                }
+                self.excluded.push(best_move);
+            }
+
+            // 2. An unfinished iteration can't be trusted, the last finished one stands
+            if self.stopped && self.result.depth > 0 {
+                break;
+            }
+
+            // a line found later can still come out ahead when the search is unstable
+            lines.sort_by_key(|line| -line.score);
+            self.result = lines[0].clone();
+            self.lines = lines;
+            let score = self.result.score;
+
+            for line in 0..self.lines.len().min(self.print_lines) {
+                println!("{}", self.info(board, line));
+            }
+
+            if self.stopped {
+                break;
+            }
+
+            // the helpers go on until stopped, the rest is the main thread's to decide
+            if self.id != 0 {
+                continue;
+            }
+
+            // a mate short enough was asked for and found
+            if let Some(moves) = self.limits.mate {
+                if score >= MATE - (2 * moves - 1) {
+                    break;
+                }
+            }
+
+            // on the clock, stop when the next iteration isn't worth starting
+            self.check_ponderhit();
+            self.time.update(self.result.best_move, score);
+            if self.time.is_timed() && (single_reply || self.time.soft_limit_reached()) {
+                break;
+            }
+        }
+    }
+
+    // the UCI info line for one line of the last finished iteration, with the nodes of every
+    // thread
+    pub fn info(&self, board: &Board, line: usize) -> SearchInfo {
+        let line_result = &self.lines[line];
+
+        SearchInfo {
+            depth: line_result.depth,
+            multipv: line + 1,
+            seldepth: self.seldepth,
+            score: line_result.score,
+            nodes: self.total_nodes(),
+            time_ms: self.time.elapsed().as_millis(),
+            hashfull: self.shared.tt.hashfull(),
+            pv: line_result.pv.clone(),
+            chess960: board.chess960,
+        }
+    }
+
+    // searches the root in a narrow window around the last iteration's score, which cuts more
+    // when the score holds, and widens the side it fell outside of until it fits
+    fn aspiration(&mut self, board: &Board, depth: i32, previous: i32) -> i32 {
+        let mut delta = self.config.aspiration_window;
+        let (mut alpha, mut beta) =
+            match self.config.aspiration_windows && depth >= self.config.aspiration_min_depth {
+                true => (previous - delta, previous + delta),
+                false => (-INFINITY, INFINITY),
+            };
+
+        loop {
+            let score = self.negamax(board, depth, alpha, beta, 0);
+            if self.stopped {
+                return score;
+            }
+
+            if score <= alpha {
+                beta = (alpha + beta) / 2;
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+
+            delta *= 2;
+        }
+    }
+
+    // the PV table's line, carried on with the hash table's best moves wherever it was cut short,
+    // as happens when a node below was cut by a transposition or a draw
+    fn principal_variation(&self, board: &Board) -> Vec<Move> {
+        let mut pv = self.pv[0][..self.pv_length[0]].to_vec();
+
+        let mut position = board.clone();
+        let mut seen = vec![position.key];
+        for m in &pv {
+            position.make_move(*m);
+            seen.push(position.key);
+        }
+
+        while pv.len() < MAX_PLY {
+            let m = match self.shared.tt.probe(position.key) {
+                Some(entry) if !entry.best_move.is_null() => entry.best_move,
+                _ => break,
+            };
+            if !position.is_move_valid(m) {
+                break;
+            }
+
+            position.make_move(m);
+            if seen.contains(&position.key) {
+                break;
+            }
+            seen.push(position.key);
+            pv.push(m);
+        }
+
+        pv
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        // the clock, the flags and the other threads' nodes are only looked at every so often,
+        // they aren't free
+        if self.nodes.is_multiple_of(1024) {
+            self.shared.nodes[self.id].store(self.nodes, Ordering::Relaxed);
+            if self.id == 0 {
+                self.check_ponderhit();
+            }
+
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self.shared.abort.load(Ordering::Relaxed)
+                || (self.id == 0 && self.time.hard_limit_reached());
+        }
+
+        if let (0, Some(nodes)) = (self.id, self.limits.nodes) {
+            if self.total_nodes() >= nodes {
+                self.stopped = true;
+            }
+        }
+
+        self.stopped
+    }
+
+    // starts the clock once the ponder flag is lowered
+    fn check_ponderhit(&mut self) {
+        if self.time.is_pondering() && !self.ponder.load(Ordering::Relaxed) {
+            self.time.ponderhit();
+        }
+    }
+
+    // this thread's nodes as they are, the others' as last published
+    fn total_nodes(&self) -> u64 {
+        self.shared.total_nodes() - self.shared.nodes[self.id].load(Ordering::Relaxed) + self.nodes
+    }
+
+    // the moves that led to the node at ply, the last one first
+    fn previous(&self, ply: usize) -> [Option<PieceTo>; 2] {
+        [
+            ply.checked_sub(1).and_then(|i| self.stack[i]),
+            ply.checked_sub(2).and_then(|i| self.stack[i]),
+        ]
+    }
+
+    // draws the search doesn't have to look into: the fifty-move rule, insufficient material and
+    // any repetition, since if repeating once is the best a side can do it can repeat again
+    fn is_draw(&self, board: &Board) -> bool {
+        if board.claimable_draw().is_some() || board.insufficient_material() {
+            return true;
+        }
+
+        // only positions with the same side to move since the last irreversible move can repeat
+        let reversible = (board.halfmove_clock as usize).min(self.keys.len() - 1);
+        self.keys[self.keys.len() - 1 - reversible..self.keys.len() - 1]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .any(|key| *key == board.key)
+    }
+
+    fn negamax(&mut self, board: &Board, depth: i32, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv_length[ply] = ply;
+        if self.should_stop() {
+            return 0;
+        }
+
+        // a side in check has few moves, and the line may be forcing, so it gets an extra ply
+        let in_check = board.checkers().any();
+        let depth = match in_check && self.config.check_extensions && ply > 0 {
+            true => depth + 1,
+            false => depth,
+        };
+
+        if depth <= 0 {
+            return self.quiescence(board, alpha, beta, ply);
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        // a node searched with an open window may become part of the principal variation,
+        // the rest only have to prove a score is above or below the window
+        let pv_node = beta - alpha > 1;
+
+        if ply > 0 && self.is_draw(board) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return board.evaluate();
+        }
+
+        // 1. A deep enough result for this position may already be known. PV nodes search on
+        // anyway, so the line they return is complete
+        let entry = self.shared.tt.probe(board.key);
+        if let Some(entry) = entry.filter(|entry| !pv_node && entry.depth >= depth) {
+            let score = score_from_tt(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        // 2. Whole-node pruning, when the static evaluation is far enough from the window that
+        // searching the moves is unlikely to change the outcome. None of it applies in check
+        let static_eval = match in_check {
+            true => -INFINITY,
+            false => board.evaluate(),
+        };
+        if !in_check && !pv_node {
+            if let Some(score) = self.prune_node(board, static_eval, depth, alpha, beta, ply) {
+                return score;
+            }
+        }
+
+        let hash_move = match (self.config.ordering.hash_move, entry) {
+            (true, Some(entry)) => entry.best_move,
+            _ => Move::NULL,
+        };
+
+        // 3. Search the moves in the picker's order
+        let previous = self.previous(ply);
+        let mut picker = MovePicker::new(board, hash_move, self.ordering.killers(ply))
+            .with_history(self.ordering.countermove(previous[0]), previous);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = Move::NULL;
+        let mut legal_moves = 0;
+        let mut quiets_tried = MoveList::new();
+
+        // near the leaves, quiets that can't raise the static evaluation to alpha are skipped
+        let futile = self.config.futility
+            && !in_check
+            && depth <= self.config.futility_max_depth
+            && static_eval + self.config.futility_margin * depth <= alpha;
+        let late_quiets = match self.config.lmp && !in_check && depth <= self.config.lmp_max_depth {
+            true => self.config.lmp_base + (depth * depth) as usize,
+            false => usize::MAX,
+        };
+
+        while let Some(m) = picker.next_move(Some(&self.ordering)) {
+            if !board.is_legal(m) || (ply == 0 && self.excluded.contains(&m)) {
+                continue;
+            }
+
+            let mut child = board.clone();
+            child.make_move(m);
+            let gives_check = child.checkers().any();
+            let quiet = m.is_quiet();
+
+            // 4. Move pruning, only once a move has been found that isn't getting mated
+            if quiet
+                && !gives_check
+                && best_score > -MATE_BOUND
+                && (futile || quiets_tried.len() >= late_quiets)
+            {
+                continue;
+            }
+
+            legal_moves += 1;
+            self.keys.push(child.key);
+            self.stack[ply] = Some(PieceTo::of(board, m));
+
+            // 5. Late move reductions: moves this far down the ordering rarely beat alpha, so
+            // they are searched shallower first, and again at full depth if they do
+            let reduction = match self.config.lmr
+                && quiet
+                && !in_check
+                && !gives_check
+                && depth >= self.config.lmr_min_depth
+                && legal_moves > self.config.lmr_full_depth_moves
+            {
+                true => {
+                    let depth_index = (depth as usize).min(MAX_PLY - 1);
+                    self.reductions[depth_index][legal_moves.min(63)].min(depth - 2)
+                }
+                false => 0,
+            };
+
+            // 6. Principal variation search: the first move is expected to be the best, so the
+            // others only get a null window to show they are worse, and a full one if they aren't
+            let mut score;
+            if legal_moves == 1 {
+                score = -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1);
+            } else {
+                score = -self.negamax(&child, depth - 1 - reduction, -alpha - 1, -alpha, ply + 1);
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(&child, depth - 1, -alpha - 1, -alpha, ply + 1);
+                }
+                if score > alpha && score < beta {
+                    score = -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1);
+                }
+            }
+
+            self.keys.pop();
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = m;
+            }
+            if score > alpha {
+                alpha = score;
+                if pv_node {
+                    self.update_pv(m, ply);
+                }
+            }
+
+            // 7. A refutation: good enough that the opponent won't allow this position
+            if alpha >= beta {
+                if quiet {
+                    self.ordering.update_quiets(
+                        board,
+                        m,
+                        quiets_tried.as_slice(),
+                        depth,
+                        ply,
+                        &previous,
+                    );
+                }
+                break;
+            }
+
+            if quiet {
+                quiets_tried.push(m);
+            }
+        }
+
+        // 8. No legal moves: mated, or stalemate. Moves are only pruned after one was searched
+        if legal_moves == 0 {
+            return match in_check {
+                true => -MATE + ply as i32,
+                false => 0,
+            };
+        }
+
+        let bound = match best_score {
+            score if score >= beta => Bound::Lower,
+            score if score > original_alpha => Bound::Exact,
+            _ => Bound::Upper,
+        };
+        let stored_move = match bound {
+            Bound::Upper => Move::NULL,
+            _ => best_move,
+        };
+
+        // a root searched without some of its moves has a score that only holds for the rest
+        if ply > 0 || self.excluded.is_empty() {
+            self.shared.tt.store(
+                board.key,
+                stored_move,
+                score_to_tt(best_score, ply),
+                depth,
+                bound,
+            );
+        }
+
+        best_score
+    }
+
+    // the line from ply is m followed by the line from the child it led to
+    fn update_pv(&mut self, m: Move, ply: usize) {
+        let length = self.pv_length[ply + 1].max(ply + 1);
+        let (parents, children) = self.pv.split_at_mut(ply + 1);
+
+        parents[ply][ply] = m;
+        parents[ply][ply + 1..length].copy_from_slice(&children[0][ply + 1..length]);
+        self.pv_length[ply] = length;
+    }
+
+    // reverse futility pruning, razoring and null move pruning, in that order. Returns the score
+    // to cut the node with, if any of them can
+    fn prune_node(
+        &mut self,
+        board: &Board,
+        static_eval: i32,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+        ply: usize,
+    ) -> Option<i32> {
+        let config = self.config;
+
+        // 1. So far above beta that losing a margin per ply still fails high
+        if config.reverse_futility
+            && depth <= config.reverse_futility_max_depth
+            && beta.abs() < MATE_BOUND
+            && static_eval - config.reverse_futility_margin * depth >= beta
+        {
+            return Some(static_eval);
+        }
+
+        // 2. So far below alpha that only captures could help, which quiescence checks
+        if config.razoring
+            && depth <= config.razoring_max_depth
+            && static_eval + config.razoring_margin * depth < alpha
+        {
+            let score = self.quiescence(board, alpha - 1, alpha, ply);
+            if score < alpha {
+                return Some(score);
+            }
+        }
+
+        // 3. Passing the turn and still failing high. Not twice in a row, and not without pieces
+        // besides pawns, where passing would often be the best move (zugzwang). The root follows
+        // no move at all
+        let after_null_move = ply
+            .checked_sub(1)
+            .is_some_and(|parent| self.stack[parent].is_none());
+        if config.null_move
+            && depth >= config.null_move_min_depth
+            && ply >= self.null_move_min_ply
+            && !after_null_move
+            && static_eval >= beta
+            && beta.abs() < MATE_BOUND
+            && board.has_non_pawn_material(board.to_move)
+        {
+            let reduction = config.null_move_reduction + depth / config.null_move_depth_divisor;
+
+            let mut child = board.clone();
+            child.make_null_move();
+            self.keys.push(child.key);
+            self.stack[ply] = None;
+
+            let score = -self.negamax(&child, depth - 1 - reduction, -beta, -beta + 1, ply + 1);
+
+            self.keys.pop();
+            if self.stopped {
+                return Some(0);
+            }
+
+            if score >= beta {
+                // a mate found after passing isn't proven, since passing isn't a move
+                let score = score.min(MATE_BOUND - 1);
+                if depth < config.null_move_verification_depth {
+                    return Some(score);
+                }
+
+                // deep cutoffs are trusted only if a search without null moves agrees
+                self.null_move_min_ply = ply + (3 * (depth - reduction) / 4) as usize;
+                let verified = self.negamax(board, depth - reduction, beta - 1, beta, ply);
+                self.null_move_min_ply = 0;
+
+                if verified >= beta {
+                    return Some(score);
+                }
+            }
+        }
+
+        None
+    }
+
+    // searches captures (or every evasion when in check) until the position is quiet, so that
+    // the static evaluation is never taken in the middle of an exchange
+    fn quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv_length[ply] = ply;
+        if self.should_stop() {
+            return 0;
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        if ply >= MAX_PLY - 1 {
+            return board.evaluate();
+        }
+
+        // 1. Standing pat: the side to move can usually do at least as well as doing nothing
+        let in_check = board.in_check(board.to_move);
+        let mut best_score = -INFINITY;
+        if !in_check {
+            best_score = board.evaluate();
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+        }
+
+        let mut moves = match in_check {
+            true => board.generate_evasions(board.to_move),
+            false => board.generate_captures(board.to_move),
+        };
+        if self.config.ordering.mvv_lva {
+            moves
+                .as_mut_slice()
+                .sort_by_key(|m| match m.is_capture() || m.is_promotion() {
+                    true => -mvv_lva(board, *m),
+                    false => 0,
+                });
+        }
+
+        // 2. Captures that lose material by exchange won't help the side making them
+        let mut legal_moves = 0;
+        for m in moves.iter().copied() {
+            if !in_check && self.config.ordering.see && board.see(m) < 0 {
+                continue;
+            }
+            if !board.is_legal(m) {
+                continue;
+            }
+            legal_moves += 1;
+
+            let mut child = board.clone();
+            child.make_move(m);
+
+            let score = -self.quiescence(&child, -beta, -alpha, ply + 1);
+            if self.stopped {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if in_check && legal_moves == 0 {
+            return -MATE + ply as i32;
+        }
+
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup_table::LookupTable;
+
+    #[test]
+    fn null_window_at_the_root() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen(crate::board::STARTING_FEN, &lookup_table);
+        let config = SearchConfig::default();
+        let flag = || Arc::new(AtomicBool::new(false));
+        let mut worker = Worker::new(0, config, Arc::new(SharedState::new(1, 1)), flag(), flag());
+        worker.start(&board, &[board.key], SearchLimits::default(), config, 0);
+
+        // the start position is far above this window, and too deep for reverse futility to cut
+        // it first, so the root tries a null move
+        let score = worker.negamax(&board, 7, -1001, -1000, 0);
+        assert!(score > -1001);
+    }
+}
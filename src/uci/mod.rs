@@ -0,0 +1,562 @@
+use crate::board::{Board, STARTING_FEN};
+use crate::game::Game;
+use crate::lookup_table::LookupTable;
+use crate::search::*;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+const ENGINE_NAME: &str = concat!("Rusty Chess Engine ", env!("CARGO_PKG_VERSION"));
+// the most the spin options advertise, anything set beyond (or below their least) is clamped
+const MAX_HASH_MB: i64 = 65536;
+const MAX_THREADS: i64 = 256;
+const MAX_MOVE_OVERHEAD_MS: i64 = 5000;
+const MAX_MULTI_PV: i64 = 256;
+
+// what the protocol loop hears about: a line from the GUI, or a search done with its result
+enum Message {
+    Command(String),
+    Finished(Box<Search>, SearchResult),
+}
+
+// Speaks the UCI protocol over stdin and stdout. Commands are read on their own thread and each
+// search runs on another, both reporting to the protocol loop through one channel, so the loop is
+// free to answer while searching and a stop ends the search at once
+pub struct Uci {
+    lookup_table: &'static LookupTable,
+    // none after a position command that couldn't be set up, until a valid one comes
+    game: Option<Game<'static>>,
+    // taken by the search thread while it runs, and handed back when it's done
+    search: Option<Search>,
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+
+    sender: Sender<Message>,
+    messages: Receiver<Message>,
+    // commands that came in while waiting for a search to end
+    pending: VecDeque<String>,
+
+    // an infinite or ponder search only sends its move once told to stop, or at ponderhit, so a
+    // result that comes back before then is held
+    infinite: bool,
+    pondering: bool,
+    held: Option<SearchResult>,
+    quit: bool,
+
+    // castles are sent and read as the king taking its rook, whatever the position
+    chess960: bool,
+}
+
+pub fn run_uci() {
+    // the search thread takes boards with it, so the lookup table they borrow has to live as long
+    // as the program
+    let lookup_table: &'static LookupTable = Box::leak(Box::new(LookupTable::new()));
+    let mut uci = Uci::new(lookup_table);
+    uci.read_input();
+
+    while !uci.quit {
+        let message = match uci.pending.pop_front() {
+            Some(line) => Message::Command(line),
+            None => match uci.messages.recv() {
+                Ok(message) => message,
+                Err(_) => break,
+            },
+        };
+
+        match message {
+            Message::Command(line) => uci.handle(&line),
+            Message::Finished(search, result) => uci.finished(*search, result),
+        }
+    }
+
+    // a search still running is stopped and its move sent before leaving
+    uci.wait_for_search();
+}
+
+impl Uci {
+    pub fn new(lookup_table: &'static LookupTable) -> Uci {
+        let mut search = Search::default();
+        search.print_info = true;
+        let (sender, messages) = mpsc::channel();
+
+        Uci {
+            lookup_table,
+            game: Some(Game::starting_position(lookup_table)),
+            stop: search.stop.clone(),
+            ponder: search.ponder.clone(),
+            search: Some(search),
+
+            sender,
+            messages,
+            pending: VecDeque::new(),
+
+            infinite: false,
+            pondering: false,
+            held: None,
+            quit: false,
+
+            chess960: false,
+        }
+    }
+
+    // reads the GUI's commands from stdin on a thread of their own
+    fn read_input(&self) {
+        let commands = self.sender.clone();
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if commands.send(Message::Command(line)).is_err() {
+                    return;
+                }
+            }
+            // the GUI going away is as good as a quit
+            let _ = commands.send(Message::Command(String::from("quit")));
+        });
+    }
+
+    pub fn handle(&mut self, line: &str) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.first().copied() {
+            Some("uci") => {
+                println!("id name {}", ENGINE_NAME);
+                println!("id author the Rusty Chess Engine developers");
+                println!(
+                    "option name Hash type spin default 16 min 1 max {}",
+                    MAX_HASH_MB
+                );
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!(
+                    "option name Move Overhead type spin default 30 min 0 max {}",
+                    MAX_MOVE_OVERHEAD_MS
+                );
+                println!("option name Ponder type check default false");
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                );
+                println!(
+                    "option name Skill Level type spin default {} min 0 max {}",
+                    MAX_SKILL_LEVEL, MAX_SKILL_LEVEL
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            // answered straight away, even while searching
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.wait_for_search();
+                self.search_mut().clear();
+            }
+            Some("setoption") => {
+                self.wait_for_search();
+                self.set_option(&tokens[1..]);
+            }
+            Some("position") => {
+                self.wait_for_search();
+                self.position(&tokens[1..]);
+            }
+            Some("go") => {
+                self.wait_for_search();
+                self.go(&tokens[1..]);
+            }
+            Some("stop") => self.stop_search(),
+            Some("ponderhit") => self.ponderhit(),
+            Some("quit") => self.quit = true,
+            Some(command) => println!("info string unknown command {}", command),
+            None => {}
+        }
+    }
+
+    fn search_mut(&mut self) -> &mut Search {
+        self.search.as_mut().expect("the search is away searching")
+    }
+
+    // setoption name <name, maybe several words> value <value>
+    fn set_option(&mut self, tokens: &[&str]) {
+        let value_at = tokens.iter().position(|t| *t == "value");
+        let name_at = 1.min(tokens.len());
+        let name = tokens[name_at..value_at.unwrap_or(tokens.len()).max(name_at)].join(" ");
+        let value = value_at
+            .map(|i| tokens[i + 1..].join(" "))
+            .unwrap_or_default();
+
+        let search = self.search_mut();
+        match (name.to_lowercase().as_str(), value.parse::<i64>()) {
+            ("hash", Ok(mb)) => search.set_hash(mb.clamp(1, MAX_HASH_MB) as usize),
+            ("threads", Ok(threads)) => search.set_threads(threads.clamp(1, MAX_THREADS) as usize),
+            ("move overhead", Ok(ms)) => {
+                search.config.move_overhead = ms.clamp(0, MAX_MOVE_OVERHEAD_MS) as u64
+            }
+            ("multipv", Ok(lines)) => {
+                search.config.multi_pv = lines.clamp(1, MAX_MULTI_PV) as usize
+            }
+            // the GUI decides when to ponder with go ponder, the option only says the engine can
+            ("ponder", _) if value == "true" || value == "false" => {}
+            ("skill level", Ok(level)) => {
+                search.skill = Skill::from_level(level.clamp(0, MAX_SKILL_LEVEL as i64) as u32)
+            }
+            ("uci_chess960", _) if value == "true" || value == "false" => {
+                self.chess960 = value == "true"
+            }
+            _ => println!("info string unknown option {} {}", name, value),
+        }
+    }
+
+    // position [startpos | fen <fen> | fischer <n | random>] [moves <moves>], where fischer sets
+    // up chess960 start position n in Scharnagl's numbering, or one at random
+    fn position(&mut self, tokens: &[&str]) {
+        // a position that can't be set up leaves none, rather than the last one, to search
+        self.game = None;
+
+        let moves_at = tokens
+            .iter()
+            .position(|t| *t == "moves")
+            .unwrap_or(tokens.len());
+        let mut start = match tokens.first() {
+            Some(&"startpos") => Board::from_fen(STARTING_FEN, self.lookup_table),
+            Some(&"fen") => {
+                let fen = tokens[1..moves_at].join(" ");
+                match Board::from_fen_strict(&fen, self.chess960, self.lookup_table) {
+                    Ok(board) => board,
+                    Err(errors) => {
+                        println!("info string invalid fen {}: {:?}", fen, errors);
+                        return;
+                    }
+                }
+            }
+            Some(&"fischer") => match tokens[1..moves_at] {
+                [n] if n.parse::<u16>().is_ok_and(|n| n < 960) => {
+                    Board::chess960_position(n.parse().unwrap(), self.lookup_table)
+                }
+                ["random"] => Board::random_chess960(&mut rand::thread_rng(), self.lookup_table),
+                _ => {
+                    println!("info string expected a chess960 position from 0 to 959, or random");
+                    return;
+                }
+            },
+            _ => {
+                println!("info string expected startpos, fen or fischer");
+                return;
+            }
+        };
+
+        // castles are king takes rook from the standard start position too
+        start.chess960 |= self.chess960;
+        let mut game = Game::new(start);
+
+        for uci in tokens.iter().skip(moves_at + 1) {
+            if let Err(error) = game.push_uci(uci) {
+                println!("info string can't play {}: {:?}", uci, error);
+                break;
+            }
+        }
+
+        self.game = Some(game);
+    }
+
+    // starts the search on its own thread, which sends the search back when done
+    fn go(&mut self, tokens: &[&str]) {
+        let Some(game) = &self.game else {
+            println!("info string no position to search, the last one was invalid");
+            println!("bestmove 0000");
+            return;
+        };
+
+        let limits = parse_limits(tokens);
+        let board = game.board().clone();
+        let history: Vec<u64> = game.keys().collect();
+        let mut search = self
+            .search
+            .take()
+            .expect("the last search wasn't waited for");
+
+        self.stop.store(false, Ordering::Relaxed);
+        self.ponder.store(limits.ponder, Ordering::Relaxed);
+        self.infinite = limits.infinite;
+        self.pondering = limits.ponder;
+
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            // a mate asked for goes to the mate solver, and to the search with whatever time is
+            // left if checks alone don't mate or the solver runs out of nodes or time
+            let start = Instant::now();
+            let result = match limits
+                .mate
+                .and_then(|moves| solve_mate(&search, &board, moves, &limits))
+            {
+                Some(result) => result,
+                None => {
+                    let limits = limits.after(board.to_move, start.elapsed());
+                    search.go(&board, &history, limits)
+                }
+            };
+            let _ = sender.send(Message::Finished(Box::new(search), result));
+        });
+    }
+
+    // the search is back: its move goes out, unless it still has to wait for a stop or ponderhit
+    fn finished(&mut self, search: Search, result: SearchResult) {
+        self.search = Some(search);
+
+        match self.infinite || self.pondering {
+            true => self.held = Some(result),
+            false => self.best_move(&result),
+        }
+    }
+
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.ponder.store(false, Ordering::Relaxed);
+        self.infinite = false;
+        self.pondering = false;
+
+        if let Some(result) = self.held.take() {
+            self.best_move(&result);
+        }
+    }
+
+    // the opponent played the expected move, so the ponder search goes on as a normal one and
+    // its clock starts now
+    fn ponderhit(&mut self) {
+        self.ponder.store(false, Ordering::Relaxed);
+        self.pondering = false;
+
+        if self.infinite {
+            return;
+        }
+        if let Some(result) = self.held.take() {
+            self.best_move(&result);
+        }
+    }
+
+    // stops a running search and waits for its move, keeping any commands that come meanwhile
+    fn wait_for_search(&mut self) {
+        self.stop_search();
+
+        while self.search.is_none() {
+            match self.messages.recv() {
+                Ok(Message::Finished(search, result)) => self.finished(*search, result),
+                Ok(Message::Command(line)) => self.pending.push_back(line),
+                Err(_) => return,
+            }
+        }
+    }
+
+    // bestmove, with the reply expected from the opponent as the move to ponder on
+    fn best_move(&self, result: &SearchResult) {
+        let chess960 = self.game.as_ref().is_some_and(|game| game.board().chess960);
+        let ponder = match result.pv.first() == Some(&result.best_move) {
+            true => result.pv.get(1),
+            false => None,
+        };
+
+        match (result.best_move.is_null(), ponder) {
+            (true, _) => println!("bestmove 0000"),
+            (false, Some(ponder)) => println!(
+                "bestmove {} ponder {}",
+                result.best_move.to_uci(chess960),
+                ponder.to_uci(chess960)
+            ),
+            (false, None) => println!("bestmove {}", result.best_move.to_uci(chess960)),
+        }
+    }
+}
+
+// Looks for the mate with the mate solver, within the go command's nodes and half its time, so
+// the search still has the other half should the solver give up
+fn solve_mate(
+    search: &Search,
+    board: &Board,
+    moves: i32,
+    limits: &SearchLimits,
+) -> Option<SearchResult> {
+    let start = Instant::now();
+    let time = TimeManager::new(limits, board.to_move, &search.config);
+
+    let mut solver = MateSolver::new(limits.nodes).with_stop(search.stop.clone());
+    if let Some(deadline) = time.deadline() {
+        solver = solver.with_deadline(start + (deadline - start) / 2);
+    }
+
+    let line = match solver.solve(board, moves.max(0) as u32) {
+        MateResult::Mate(line) => line,
+        _ => return None,
+    };
+
+    let info = SearchInfo {
+        depth: line.len() as i32,
+        multipv: 1,
+        seldepth: line.len(),
+        score: MATE - line.len() as i32,
+        nodes: solver.nodes(),
+        time_ms: start.elapsed().as_millis(),
+        hashfull: search.hashfull(),
+        pv: line.clone(),
+        chess960: board.chess960,
+    };
+    println!("{}", info);
+
+    Some(SearchResult {
+        best_move: *line.first()?,
+        score: info.score,
+        depth: info.depth,
+        nodes: info.nodes,
+        pv: line,
+    })
+}
+
+// the limits of a go command, ignoring anything it doesn't know
+pub fn parse_limits(tokens: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut tokens = tokens.iter();
+
+    while let Some(token) = tokens.next() {
+        // clocks can run negative by the time a GUI sends them, which counts as no time left
+        let mut value = || {
+            tokens
+                .next()
+                .and_then(|value| value.parse::<i64>().ok())
+                .map(|value| value.max(0) as u64)
+        };
+
+        match *token {
+            "depth" => limits.depth = value().map(|v| v as i32),
+            "nodes" => limits.nodes = value(),
+            "mate" => limits.mate = value().map(|v| v as i32),
+            "movetime" => limits.movetime = value(),
+            "wtime" => limits.wtime = value(),
+            "btime" => limits.btime = value(),
+            "winc" => limits.winc = value(),
+            "binc" => limits.binc = value(),
+            "movestogo" => limits.movestogo = value().map(|v| v as u32),
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            _ => {}
+        }
+    }
+
+    limits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::*;
+
+    fn uci() -> Uci {
+        Uci::new(Box::leak(Box::new(LookupTable::new())))
+    }
+
+    #[test]
+    fn chess960_x_fen_with_uci_chess960() {
+        let fen = "rnbkqbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKQBNR w KQkq - 0 1";
+        let mut uci = uci();
+        uci.handle("setoption name UCI_Chess960 value true");
+        uci.handle(&format!("position fen {}", fen));
+
+        // KQkq are the outermost rooks, on either side of the king on d1
+        let board = uci
+            .game
+            .as_ref()
+            .expect("the position was rejected")
+            .board();
+        assert!(board.chess960);
+        assert_eq!(
+            board.castling_rights.rook_file(CASTLE::WhiteKingside),
+            Some(FILE::FileH)
+        );
+        assert_eq!(
+            board.castling_rights.rook_file(CASTLE::WhiteQueenside),
+            Some(FILE::FileA)
+        );
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn spin_options_are_clamped() {
+        let mut uci = uci();
+        uci.handle("setoption name Skill Level value -3");
+        assert_eq!(uci.search_mut().skill, Skill::from_level(0));
+        // would wrap around to level 5 as a u32
+        uci.handle("setoption name Skill Level value 4294967301");
+        assert_eq!(uci.search_mut().skill, Skill::from_level(MAX_SKILL_LEVEL));
+
+        uci.handle("setoption name MultiPV value 0");
+        assert_eq!(uci.search_mut().config.multi_pv, 1);
+        uci.handle("setoption name MultiPV value 1000");
+        assert_eq!(uci.search_mut().config.multi_pv, MAX_MULTI_PV as usize);
+        uci.handle("setoption name Move Overhead value -50");
+        assert_eq!(uci.search_mut().config.move_overhead, 0);
+    }
+
+    // the move the search thread sends back for the last go
+    fn search_result(uci: &mut Uci) -> SearchResult {
+        match uci.messages.recv().expect("the search thread went away") {
+            Message::Finished(search, result) => {
+                uci.search = Some(*search);
+                result
+            }
+            Message::Command(line) => panic!("no search running, got {}", line),
+        }
+    }
+
+    #[test]
+    fn mate_is_solved_within_its_limits() {
+        let lookup_table = LookupTable::new();
+        let board = Board::from_fen("2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1", &lookup_table);
+        let search = Search::default();
+
+        let limits = parse_limits(&["mate", "2"]);
+        let result = solve_mate(&search, &board, 2, &limits).expect("no mate found");
+        assert_eq!(result.best_move.to_uci(false), "d2d8");
+        assert_eq!(result.score, MATE - 3);
+
+        // two nodes don't settle it
+        let limits = parse_limits(&["mate", "2", "nodes", "2"]);
+        assert!(solve_mate(&search, &board, 2, &limits).is_none());
+    }
+
+    #[test]
+    fn go_mate_falls_back_to_the_search() {
+        let mut uci = uci();
+
+        // the solver runs out of nodes, and the search plays whatever it has in the rest
+        uci.handle("position fen 2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1");
+        uci.handle("go mate 2 nodes 2");
+        let result = search_result(&mut uci);
+        let board = uci.game.as_ref().unwrap().board();
+        assert!(board.is_legal(result.best_move));
+
+        // the key is quiet, so checks alone don't mate but the search does
+        uci.handle("position fen k2K4/8/8/8/6n1/8/8/2R5 w - - 0 1");
+        uci.handle("go mate 2");
+        let result = search_result(&mut uci);
+        assert_eq!(result.best_move.to_uci(false), "d8c7");
+        assert_eq!(result.score, MATE - 3);
+    }
+
+    #[test]
+    fn invalid_position_is_not_searched() {
+        let mut uci = uci();
+        uci.handle("position startpos moves e2e4");
+
+        // standard chess has the king on e1 to castle
+        uci.handle("position fen rnbkqbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKQBNR w KQkq - 0 1");
+        assert!(uci.game.is_none());
+
+        // go answers at once instead of searching the last position
+        uci.handle("go depth 1");
+        assert!(uci.search.is_some());
+
+        uci.handle("position startpos");
+        assert!(uci.game.is_some());
+    }
+}